@@ -1,39 +1,117 @@
+pub mod component;
+pub mod diagnostics;
+pub mod formatter;
 mod highlight;
+pub mod index;
+pub mod manifest;
+pub mod settings;
 pub mod view;
 pub mod workspace;
 
+use crate::app_state::component::{ComponentStructs, find_component_structs};
+use crate::app_state::diagnostics::DiagnosticCache;
 use crate::app_state::highlight::Highlight;
+use crate::app_state::index::{ViewIndex, ViewSummary};
+use crate::app_state::settings::Settings;
 use crate::app_state::view::View;
-use crate::app_state::workspace::Workspace;
+use crate::app_state::workspace::Workspaces;
 use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex, RwLock};
-use tower_lsp::lsp_types::{CompletionItem, CompletionItemKind, InsertTextFormat, Url};
+use tokio::sync::watch;
+use tower_lsp::lsp_types::{ClientCapabilities, CompletionItem, CompletionItemKind, InsertTextFormat, Range, ResourceOperationKind, Url};
 use tracing::debug;
 use tree_sitter::{Language, Parser};
 use tree_sitter_highlight::HighlightConfiguration;
 
 pub struct AppState {
-    pub workspace: RwLock<Workspace>,
+    pub workspace: RwLock<Workspaces>,
     pub parser: Mutex<Parser>,
     pub highlight: Highlight,
     pub views: Arc<RwLock<HashMap<String, View>>>,
+    pub index: Arc<RwLock<ViewIndex>>,
+    // bumped whenever something diagnostics depend on changes
+    pub index_changes: Arc<watch::Sender<u64>>,
+    pub diagnostic_cache: Arc<Mutex<DiagnosticCache>>,
+    pub component_structs: Arc<RwLock<ComponentStructs>>,
+    pub settings: RwLock<Settings>,
+    pub client_capabilities: RwLock<ClientCapabilities>,
     pub completion_items: Vec<CompletionItem>,
     pub language: Language,
 }
 
+struct WorkspaceIndexer {
+    files: Vec<PathBuf>,
+    members: Vec<(PathBuf, Vec<PathBuf>)>,
+    language: Language,
+    views: Arc<RwLock<HashMap<String, View>>>,
+    index: Arc<RwLock<ViewIndex>>,
+    index_changes: Arc<watch::Sender<u64>>,
+    diagnostic_cache: Arc<Mutex<DiagnosticCache>>,
+    component_structs: Arc<RwLock<ComponentStructs>>,
+}
+
+// drops the cached diagnostics of the stale files, or all of them, before waking up the workspace diagnostics;
+// both happen under the cache lock so that diagnostics computed before the change aren't cached after it
+fn index_changed(diagnostic_cache: &Mutex<DiagnosticCache>, index_changes: &watch::Sender<u64>, stale: Option<&[PathBuf]>) {
+    let Ok(mut cache) = diagnostic_cache.lock() else {
+        debug!("diagnostic cache is locked");
+        index_changes.send_modify(|x| *x += 1);
+        return;
+    };
+
+    match stale {
+        Some(paths) => cache.retain(|path, _| !paths.contains(path)),
+        None => cache.clear(),
+    }
+    index_changes.send_modify(|x| *x += 1);
+}
+
+fn index_component_structs(members: &[(PathBuf, Vec<PathBuf>)], component_structs: &RwLock<ComponentStructs>) {
+    let structs = members
+        .iter()
+        .flat_map(|(src_path, views_paths)| find_component_structs(src_path, views_paths))
+        .collect::<ComponentStructs>();
+    debug!("Found {} component structs", structs.len());
+
+    if let Ok(mut component_structs) = component_structs.write() {
+        *component_structs = structs;
+    }
+}
+
+impl WorkspaceIndexer {
+    fn run(self) {
+        index_component_structs(&self.members, &self.component_structs);
+
+        let summaries = ViewSummary::index_files(&self.files, &self.language);
+        debug!("Indexed {} views", summaries.len());
+
+        if let Ok(views) = self.views.read()
+            && let Ok(mut index) = self.index.write()
+        {
+            let open_paths: HashSet<PathBuf> = views.keys().filter_map(|uri| Url::parse(uri).ok()?.to_file_path().ok()).collect();
+
+            index.retain(|path, _| open_paths.contains(path));
+            index.extend(summaries.into_iter().filter(|(path, _)| !open_paths.contains(path)));
+        }
+
+        index_changed(&self.diagnostic_cache, &self.index_changes, None);
+    }
+}
+
 impl AppState {
-    pub fn new(
-        parser: Parser,
-        highlight: Highlight,
-        completion_items: Vec<CompletionItem>,
-        language: Language,
-    ) -> Self {
+    pub fn new(parser: Parser, highlight: Highlight, completion_items: Vec<CompletionItem>, language: Language) -> Self {
         Self {
-            workspace: RwLock::new(Workspace::default()),
+            workspace: RwLock::new(Workspaces::default()),
             parser: Mutex::new(parser),
             highlight,
             views: Arc::new(RwLock::new(HashMap::new())),
+            index: Arc::new(RwLock::new(ViewIndex::new())),
+            index_changes: Arc::new(watch::Sender::new(0)),
+            diagnostic_cache: Arc::new(Mutex::new(DiagnosticCache::new())),
+            component_structs: Arc::new(RwLock::new(ComponentStructs::new())),
+            settings: RwLock::new(Settings::default()),
+            client_capabilities: RwLock::new(ClientCapabilities::default()),
             completion_items,
             language,
         }
@@ -74,21 +152,9 @@ impl AppState {
         )
         .unwrap();
 
-        let mut cn: HashSet<String> = highlight_config
-            .names()
-            .iter()
-            .map(|s| s.to_string())
-            .collect();
-        let cnr: HashSet<String> = highlight_config_rust
-            .names()
-            .iter()
-            .map(|s| s.to_string())
-            .collect();
-        let cnh: HashSet<String> = highlight_config_html
-            .names()
-            .iter()
-            .map(|s| s.to_string())
-            .collect();
+        let mut cn: HashSet<String> = highlight_config.names().iter().map(|s| s.to_string()).collect();
+        let cnr: HashSet<String> = highlight_config_rust.names().iter().map(|s| s.to_string()).collect();
+        let cnh: HashSet<String> = highlight_config_html.names().iter().map(|s| s.to_string()).collect();
 
         cn.extend(cnr);
         cn.extend(cnh);
@@ -100,27 +166,371 @@ impl AppState {
         highlight_config_html.configure(final_capture_names.as_ref());
 
         let mut highlights = Highlight::new(highlight_config, final_capture_names.clone());
-        highlights
-            .highlight_injects
-            .insert("rust", highlight_config_rust);
-        highlights
-            .highlight_injects
-            .insert("html", highlight_config_html);
+        highlights.highlight_injects.insert("rust", highlight_config_rust);
+        highlights.highlight_injects.insert("html", highlight_config_html);
 
         Self::new(parser, highlights, Self::completion_items(), lang)
     }
 
     pub fn find_layout(&self, uri: &Url, layout_name: Option<&str>) -> Option<PathBuf> {
         let file_path = uri.to_file_path().ok()?;
+        self.find_layout_by_path(&file_path, layout_name)
+    }
 
+    pub fn find_layout_by_path(&self, file_path: &Path, layout_name: Option<&str>) -> Option<PathBuf> {
         if let Ok(workspace) = self.workspace.read() {
             layout_name
                 .and_then(|layout_name| {
-                    let member = workspace.get_member_by_view(&file_path)?;
-                    let layout_path = member.views_path.join(layout_name);
-                    Some(layout_path)
+                    let member = workspace.get_member_by_view(file_path)?;
+                    member.views_file(layout_name)
+                })
+                .or_else(|| workspace.get_layout_path_by_view(file_path))
+        } else {
+            debug!("workspace is not initialized or locked");
+            None
+        }
+    }
+
+    pub fn view_roots(&self, file_path: &Path) -> Vec<PathBuf> {
+        if let Ok(workspace) = self.workspace.read() {
+            workspace.view_roots(file_path)
+        } else {
+            debug!("workspace is not initialized or locked");
+            Vec::new()
+        }
+    }
+
+    pub fn component_parameters(&self, component_path: &Path) -> Vec<(String, Option<String>, bool)> {
+        if let Ok(component_structs) = self.component_structs.read()
+            && let Some(fields) = component_structs.get(component_path)
+        {
+            return fields
+                .iter()
+                .map(|(name, type_)| (name.clone(), Some(type_.clone()), !type_.starts_with("Option<")))
+                .collect();
+        }
+
+        if let Ok(index) = self.index.read()
+            && let Some(summary) = index.get(component_path)
+        {
+            // without a struct the reads of the template are only a guess, so none of them is required
+            return summary.parameters.iter().map(|name| (name.clone(), None, false)).collect();
+        }
+
+        Vec::new()
+    }
+
+    pub fn find_child_views(&self, layout_path: &Path) -> Vec<(PathBuf, ViewSummary)> {
+        let Ok(index) = self.index.read() else {
+            debug!("index is locked");
+            return Vec::new();
+        };
+
+        index
+            .iter()
+            .filter(|(path, summary)| {
+                summary
+                    .extends
+                    .as_ref()
+                    .is_some_and(|extends| self.find_layout_by_path(path, extends.as_deref()).as_deref() == Some(layout_path))
+            })
+            .map(|(path, summary)| (path.clone(), summary.clone()))
+            .collect()
+    }
+
+    pub fn section_locations(&self, file_path: &Path, name: &str, layout_path: Option<&Path>) -> Vec<(PathBuf, Range)> {
+        let named = |path: &Path, names: &[(String, Range)]| {
+            names
+                .iter()
+                .filter(|(x, _)| x == name)
+                .map(|(_, range)| (path.to_path_buf(), *range))
+                .collect::<Vec<_>>()
+        };
+
+        let Some(layout_path) = layout_path else {
+            return match self.index.read() {
+                Ok(index) => index
+                    .get(file_path)
+                    .map(|summary| named(file_path, &summary.sections))
+                    .unwrap_or_default(),
+                Err(_) => Vec::new(),
+            };
+        };
+
+        let mut locations = self
+            .find_child_views(layout_path)
+            .into_iter()
+            .flat_map(|(path, summary)| named(&path, &summary.sections))
+            .collect::<Vec<_>>();
+
+        if let Ok(index) = self.index.read()
+            && let Some(layout) = index.get(layout_path)
+        {
+            locations.extend(named(layout_path, &layout.renders));
+        }
+
+        locations
+    }
+
+    pub fn path_references(&self, target_path: &Path) -> Vec<(PathBuf, Range)> {
+        let Ok(index) = self.index.read() else {
+            debug!("index is locked");
+            return Vec::new();
+        };
+
+        index
+            .iter()
+            .flat_map(|(path, summary)| {
+                summary
+                    .path_directives
+                    .iter()
+                    .filter(|(kind, directive_path, _)| {
+                        directive_path.is_some()
+                            && self.resolve_directive_path(path, kind, directive_path.as_deref()).as_deref() == Some(target_path)
+                    })
+                    .map(|(_, _, range)| (path.clone(), *range))
+            })
+            .collect()
+    }
+
+    pub fn dependent_views(&self, changed_paths: &[PathBuf]) -> Vec<String> {
+        let dependents = self.dependent_paths(changed_paths);
+
+        let Ok(views) = self.views.read() else {
+            debug!("views are locked");
+            return Vec::new();
+        };
+
+        views
+            .keys()
+            .filter(|uri| {
+                Url::parse(uri)
+                    .ok()
+                    .and_then(|uri| uri.to_file_path().ok())
+                    .is_some_and(|path| dependents.contains(&path))
+            })
+            .cloned()
+            .collect()
+    }
+
+    // the indexed files with a directive that points to one of the changed paths
+    pub fn dependent_paths(&self, changed_paths: &[PathBuf]) -> Vec<PathBuf> {
+        let Ok(index) = self.index.read() else {
+            debug!("index is locked");
+            return Vec::new();
+        };
+
+        index
+            .iter()
+            .filter(|(path, summary)| {
+                summary.path_directives.iter().any(|(kind, directive_path, _)| {
+                    self.directive_candidates(path, kind, directive_path.as_deref())
+                        .iter()
+                        .any(|candidate| changed_paths.contains(candidate))
                 })
-                .or_else(|| workspace.get_layout_path_by_view(&file_path))
+            })
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    // every file a directive could point to, whether or not it exists right now
+    fn directive_candidates(&self, file_path: &Path, kind: &str, path: Option<&str>) -> Vec<PathBuf> {
+        match (kind, path) {
+            ("extends_directive", path) => self.find_layout_by_path(file_path, path).into_iter().collect(),
+            (_, Some(path)) => {
+                let mut candidates = self.view_roots(file_path).iter().map(|root| root.join(path)).collect::<Vec<_>>();
+                candidates.extend(file_path.parent().map(|parent| parent.join(path)));
+                candidates
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn layout_paths(&self, paths: &[PathBuf]) -> Vec<PathBuf> {
+        let Ok(index) = self.index.read() else {
+            debug!("index is locked");
+            return Vec::new();
+        };
+
+        paths
+            .iter()
+            .filter_map(|path| {
+                let extends = index.get(path)?.extends.as_ref()?;
+                self.find_layout_by_path(path, extends.as_deref())
+            })
+            .collect()
+    }
+
+    pub fn refresh_layouts(&self) {
+        if let Ok(mut views) = self.views.write()
+            && let Ok(index) = self.index.read()
+        {
+            for (uri, view) in views.iter_mut() {
+                let Some(path) = Url::parse(uri).ok().and_then(|uri| uri.to_file_path().ok()) else {
+                    continue;
+                };
+
+                view.layout_path = index
+                    .get(&path)
+                    .and_then(|summary| summary.extends.as_ref())
+                    .and_then(|extends| self.find_layout_by_path(&path, extends.as_deref()));
+            }
+        }
+    }
+
+    pub fn pull_diagnostics(&self) -> bool {
+        self.client_capabilities.read().is_ok_and(|capabilities| {
+            capabilities
+                .text_document
+                .as_ref()
+                .is_some_and(|text_document| text_document.diagnostic.is_some())
+        })
+    }
+
+    pub fn rename_file_support(&self) -> bool {
+        self.client_capabilities.read().is_ok_and(|capabilities| {
+            capabilities
+                .workspace
+                .as_ref()
+                .and_then(|workspace| workspace.workspace_edit.as_ref())
+                .and_then(|workspace_edit| workspace_edit.resource_operations.as_ref())
+                .is_some_and(|operations| operations.contains(&ResourceOperationKind::Rename))
+        })
+    }
+
+    pub fn update_index(&self, uri: &Url, summary: ViewSummary) {
+        let Ok(path) = uri.to_file_path() else {
+            return;
+        };
+
+        let layouts = self.layout_paths(std::slice::from_ref(&path));
+        if let Ok(mut index) = self.index.write() {
+            index.insert(path.clone(), summary);
+        }
+
+        self.view_changed(path, layouts);
+    }
+
+    pub fn index_changed(&self) {
+        index_changed(&self.diagnostic_cache, &self.index_changes, None);
+    }
+
+    // a view's diagnostics are read by the views pointing to it and by the layouts it extends, before and after the change
+    fn view_changed(&self, path: PathBuf, mut stale: Vec<PathBuf>) {
+        let changed = [path];
+        stale.extend(self.layout_paths(&changed));
+        stale.extend(self.dependent_paths(&changed));
+        stale.extend(changed);
+
+        index_changed(&self.diagnostic_cache, &self.index_changes, Some(&stale));
+    }
+
+    // what the index holds for an open view, or a fresh summary for views outside the file system
+    pub fn view_summary(&self, uri: &Url, view: &View) -> ViewSummary {
+        let indexed = uri.to_file_path().ok().and_then(|path| {
+            let index = self.index.read().ok()?;
+            index.get(&path).cloned()
+        });
+
+        indexed.unwrap_or_else(|| ViewSummary::new(&view.tree, &self.language, &view.source))
+    }
+
+    pub fn index_file(&self, path: &Path) {
+        let summary = if let Ok(mut parser) = self.parser.lock() {
+            ViewSummary::from_file(path, &mut parser, &self.language).ok()
+        } else {
+            debug!("parser is locked");
+            return;
+        };
+
+        let layouts = self.layout_paths(&[path.to_path_buf()]);
+        if let Ok(mut index) = self.index.write() {
+            match summary {
+                Some(summary) => index.insert(path.to_path_buf(), summary),
+                None => index.remove(path),
+            };
+        }
+
+        self.view_changed(path.to_path_buf(), layouts);
+    }
+
+    pub async fn index_workspace(&self) {
+        if let Some(indexer) = self.workspace_indexer()
+            && let Err(e) = tokio::task::spawn_blocking(move || indexer.run()).await
+        {
+            debug!("Workspace indexing failed: {}", e);
+        }
+    }
+
+    pub fn index_workspace_blocking(&self) {
+        if let Some(indexer) = self.workspace_indexer() {
+            indexer.run();
+        }
+    }
+
+    pub async fn index_components(&self) {
+        let Some(members) = self.component_members() else {
+            return;
+        };
+
+        let component_structs = self.component_structs.clone();
+        if let Err(e) = tokio::task::spawn_blocking(move || index_component_structs(&members, &component_structs)).await {
+            debug!("Component indexing failed: {}", e);
+        }
+
+        self.index_changed();
+    }
+
+    // the source directory and views directories of every member
+    fn component_members(&self) -> Option<Vec<(PathBuf, Vec<PathBuf>)>> {
+        if let Ok(workspace) = self.workspace.read() {
+            Some(
+                workspace
+                    .members()
+                    .map(|member| (member.path.join("src"), member.views_paths.clone()))
+                    .collect(),
+            )
+        } else {
+            debug!("workspace is not initialized or locked");
+            None
+        }
+    }
+
+    fn workspace_indexer(&self) -> Option<WorkspaceIndexer> {
+        let files = if let Ok(workspace) = self.workspace.read() {
+            workspace.view_files()
+        } else {
+            debug!("workspace is not initialized or locked");
+            return None;
+        };
+
+        Some(WorkspaceIndexer {
+            files,
+            members: self.component_members()?,
+            language: self.language.clone(),
+            views: self.views.clone(),
+            index: self.index.clone(),
+            index_changes: self.index_changes.clone(),
+            diagnostic_cache: self.diagnostic_cache.clone(),
+            component_structs: self.component_structs.clone(),
+        })
+    }
+
+    pub fn resolve_directive_path(&self, file_path: &Path, kind: &str, path: Option<&str>) -> Option<PathBuf> {
+        match kind {
+            "extends_directive" => self.find_layout_by_path(file_path, path).filter(|layout| layout.is_file()),
+            _ => {
+                let workspace = self.workspace.read().ok()?;
+                workspace.resolve_view_path(file_path, path?)
+            }
+        }
+    }
+
+    pub fn find_view(&self, uri: &Url, path: &str) -> Option<PathBuf> {
+        let file_path = uri.to_file_path().ok()?;
+
+        if let Ok(workspace) = self.workspace.read() {
+            workspace.resolve_view_path(&file_path, path)
         } else {
             debug!("workspace is not initialized or locked");
             None
@@ -151,9 +561,7 @@ impl AppState {
         let match_ = CompletionItem {
             label: "match".to_string(),
             kind: Some(CompletionItemKind::SNIPPET),
-            insert_text: Some(
-                "match ${1:expression} {\n\t${2:pattern} => {\n\t\t$0\n\t},\n}".to_string(),
-            ),
+            insert_text: Some("match ${1:expression} {\n\t${2:pattern} => {\n\t\t$0\n\t},\n}".to_string()),
             insert_text_format: Some(InsertTextFormat::SNIPPET),
             detail: Some("match statement".to_string()),
             sort_text: Some("03".to_string()),
@@ -173,7 +581,7 @@ impl AppState {
         let include_ = CompletionItem {
             label: "include".to_string(),
             kind: Some(CompletionItemKind::SNIPPET),
-            insert_text: Some(r#"include("${1:path/to/other.rs.html}")"#.to_string()),
+            insert_text: Some(r#"include("$1")"#.to_string()),
             insert_text_format: Some(InsertTextFormat::SNIPPET),
             detail: Some("include directive".to_string()),
             sort_text: Some("05".to_string()),
@@ -183,9 +591,7 @@ impl AppState {
         let use_as_ = CompletionItem {
             label: "use .. as".to_string(),
             kind: Some(CompletionItemKind::SNIPPET),
-            insert_text: Some(
-                r#"use "${1:path/to/component.rs.html}" as ${2:Component}"#.to_string(),
-            ),
+            insert_text: Some(r#"use "$1" as ${2:Component}"#.to_string()),
             insert_text_format: Some(InsertTextFormat::SNIPPET),
             detail: Some("use .. as directive".to_string()),
             sort_text: Some("06".to_string()),
@@ -195,7 +601,7 @@ impl AppState {
         let use_ = CompletionItem {
             label: "use".to_string(),
             kind: Some(CompletionItemKind::SNIPPET),
-            insert_text: Some(r#"use "${1:path/to/component.rs.html}""#.to_string()),
+            insert_text: Some(r#"use "$1""#.to_string()),
             insert_text_format: Some(InsertTextFormat::SNIPPET),
             detail: Some("use directive".to_string()),
             sort_text: Some("07".to_string()),
@@ -268,3 +674,40 @@ impl AppState {
         ]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::temp_files;
+
+    #[test]
+    fn indexing_keeps_summaries_of_open_views() {
+        let dir = temp_files(&[
+            ("Cargo.toml", "[package]\nname = \"app\"\n"),
+            ("views/open.rs.html", "<p>disk</p>\n"),
+            ("views/closed.rs.html", "<p>disk</p>\n"),
+        ]);
+        let root = dir.path();
+
+        let state = AppState::setup();
+        state.workspace.write().unwrap().load_folder(root).unwrap();
+        let indexer = state.workspace_indexer().unwrap();
+
+        // the editor opens a view while the indexer is still running
+        let uri = Url::from_file_path(root.join("views/open.rs.html")).unwrap();
+        let source = "<p>editor</p>\n".to_string();
+        let tree = state.parser.lock().unwrap().parse(&source, None).unwrap();
+        state.views.write().unwrap().insert(uri.to_string(), View::new(source, tree, 1));
+        let summary = ViewSummary {
+            parameters: vec!["editor".to_string()],
+            ..Default::default()
+        };
+        state.update_index(&uri, summary);
+
+        indexer.run();
+
+        let index = state.index.read().unwrap();
+        assert_eq!(index[&root.join("views/open.rs.html")].parameters, ["editor"]);
+        assert!(index.contains_key(&root.join("views/closed.rs.html")));
+    }
+}
@@ -0,0 +1,327 @@
+use crate::app_state::workspace::views_file;
+use crate::backend::tree_extensions::TreeExtensions;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tree_sitter::{Language, Node, Parser, Tree};
+
+pub type ComponentStructs = HashMap<PathBuf, Vec<(String, String)>>;
+
+pub fn template_parameters(tree: &Tree, source: &str) -> Vec<String> {
+    let mut snippets = Vec::new();
+    collect_snippets(tree.root_node(), source, &mut snippets);
+
+    snippet_parameters(&snippets)
+}
+
+fn snippet_parameters(snippets: &[String]) -> Vec<String> {
+    let mut parser = Parser::new();
+    if snippets.is_empty() || parser.set_language(&Language::new(tree_sitter_rust::LANGUAGE)).is_err() {
+        return Vec::new();
+    }
+
+    // every snippet becomes a function, so one rust parse covers the whole template
+    let code = snippets
+        .iter()
+        .enumerate()
+        .map(|(i, snippet)| format!("fn __rshtml_{i}() {{\n{snippet}\n}}\n"))
+        .collect::<String>();
+
+    let Some(rust_tree) = parser.parse(&code, None) else {
+        return Vec::new();
+    };
+
+    let mut reads = Vec::new();
+    let mut bindings = HashSet::new();
+    collect_identifiers(rust_tree.root_node(), &code, false, &mut reads, &mut bindings);
+
+    let mut parameters = Vec::new();
+    for read in reads {
+        if !bindings.contains(&read) && !parameters.contains(&read) {
+            parameters.push(read);
+        }
+    }
+
+    parameters
+}
+
+// rust code of the template, each piece wrapped so it parses on its own
+fn collect_snippets(node: Node, source: &str, snippets: &mut Vec<String>) {
+    let text = |field: &str| {
+        node.child_by_field_name(field)
+            .and_then(|x| x.utf8_text(source.as_bytes()).ok())
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+    };
+    let head = |keyword: &str| Tree::statement_head(node, source, keyword);
+
+    let snippet = match node.kind() {
+        "rust_expr_simple" | "rust_expr_paren" => text("expr").map(|expr| format!("({expr});")),
+        "rust_block" => text("content").map(str::to_string),
+        "if_stmt" => head("if").map(|head| format!("if {head} {{}}")),
+        "while_stmt" => head("while").map(|head| format!("while {head} {{}}")),
+        "for_stmt" => head("for").map(|head| format!("for {head} {{}}")),
+        "match_stmt" => head("match").map(|head| format!("match {head} {{}}")),
+        "match_stmt_arm" => text("pattern").map(|pattern| format!("match () {{ {pattern} => {{}} }}")),
+        "else_clause" => head("else")
+            .and_then(|head| head.strip_prefix("if ").map(|x| x.trim().to_string()))
+            .map(|head| format!("if {head} {{}}")),
+        _ => None,
+    };
+    snippets.extend(snippet);
+
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        collect_snippets(child, source, snippets);
+    }
+}
+
+fn collect_identifiers(node: Node, code: &str, binding: bool, reads: &mut Vec<String>, bindings: &mut HashSet<String>) {
+    let text = |node: Node| node.utf8_text(code.as_bytes()).unwrap_or_default().to_string();
+
+    match node.kind() {
+        "identifier" | "shorthand_field_identifier" => {
+            let name = text(node);
+            if name.starts_with(char::is_uppercase) || name.starts_with("__rshtml_") {
+                return;
+            }
+
+            if binding {
+                bindings.insert(name);
+            } else if node.kind() == "identifier" && !is_path_or_call(node) {
+                reads.push(name);
+            }
+            return;
+        }
+        // `self.field` reads a field of the component
+        "field_expression" if !binding && node.child_by_field_name("value").is_some_and(|value| value.kind() == "self") => {
+            reads.extend(node.child_by_field_name("field").map(text));
+            return;
+        }
+        "lifetime" | "label" => return,
+        "scoped_identifier" | "scoped_type_identifier" | "macro_invocation" if binding => return,
+        _ => {}
+    }
+
+    let mut cursor = node.walk();
+    for (i, child) in node.children(&mut cursor).enumerate() {
+        if !child.is_named() {
+            continue;
+        }
+
+        let field = node.field_name_for_child(i as u32);
+        let child_binding = binding
+            || match node.kind() {
+                "let_declaration" | "let_condition" | "for_expression" | "match_arm" => field == Some("pattern"),
+                "closure_expression" => field == Some("parameters"),
+                _ => false,
+            };
+
+        collect_identifiers(child, code, child_binding, reads, bindings);
+    }
+}
+
+// function and macro names, paths and tokens after `.` inside macro arguments aren't template parameters
+fn is_path_or_call(node: Node) -> bool {
+    let Some(parent) = node.parent() else {
+        return false;
+    };
+
+    match parent.kind() {
+        "scoped_identifier" | "scoped_type_identifier" | "macro_invocation" => true,
+        "call_expression" => parent.child_by_field_name("function") == Some(node),
+        "token_tree" => node.prev_sibling().is_some_and(|previous| matches!(previous.kind(), "." | "::")),
+        _ => false,
+    }
+}
+
+pub fn find_component_structs(src_path: &Path, views_paths: &[PathBuf]) -> ComponentStructs {
+    let mut structs = ComponentStructs::new();
+
+    let mut parser = Parser::new();
+    if parser.set_language(&Language::new(tree_sitter_rust::LANGUAGE)).is_err() {
+        return structs;
+    }
+
+    let mut files = Vec::new();
+    collect_rust_files(src_path, &mut files);
+
+    for file in files {
+        let Ok(source) = fs::read_to_string(&file) else {
+            continue;
+        };
+
+        if !source.contains("rshtml") {
+            continue;
+        }
+
+        if let Some(tree) = parser.parse(&source, None) {
+            collect_structs(tree.root_node(), &source, views_paths, &mut structs);
+        }
+    }
+
+    structs
+}
+
+fn collect_rust_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_rust_files(&path, files);
+        } else if path.extension().is_some_and(|extension| extension == "rs") {
+            files.push(path);
+        }
+    }
+}
+
+fn collect_structs(node: Node, source: &str, views_paths: &[PathBuf], structs: &mut ComponentStructs) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        if child.kind() == "struct_item"
+            && let Some(template_path) = struct_template_path(child, source)
+            && let Some(template_path) = views_file(views_paths, &template_path)
+            && let Some(body) = child.child_by_field_name("body")
+        {
+            let mut body_cursor = body.walk();
+            let fields = body
+                .named_children(&mut body_cursor)
+                .filter(|field| field.kind() == "field_declaration")
+                .filter_map(|field| {
+                    let name = field.child_by_field_name("name")?.utf8_text(source.as_bytes()).ok()?;
+                    let type_ = field.child_by_field_name("type")?.utf8_text(source.as_bytes()).ok()?;
+                    Some((name.to_string(), type_.to_string()))
+                })
+                .collect();
+
+            structs.insert(template_path, fields);
+        } else if child.kind() == "mod_item"
+            && let Some(body) = child.child_by_field_name("body")
+        {
+            collect_structs(body, source, views_paths, structs);
+        }
+    }
+}
+
+fn struct_template_path(struct_item: Node, source: &str) -> Option<String> {
+    let mut sibling = struct_item.prev_named_sibling();
+
+    while let Some(attribute_item) = sibling.filter(|x| x.kind() == "attribute_item") {
+        if let Some(path) = attribute_item.named_child(0).and_then(|attribute| rshtml_path(attribute, source)) {
+            return Some(path);
+        }
+
+        sibling = attribute_item.prev_named_sibling();
+    }
+
+    None
+}
+
+// the template path of `#[rshtml(path = "...")]`
+fn rshtml_path(attribute: Node, source: &str) -> Option<String> {
+    let name = attribute.named_child(0)?;
+    if name.kind() != "identifier" || name.utf8_text(source.as_bytes()).ok()? != "rshtml" {
+        return None;
+    }
+
+    let arguments = attribute.child_by_field_name("arguments")?;
+    let mut cursor = arguments.walk();
+    let tokens = arguments.children(&mut cursor).collect::<Vec<_>>();
+
+    tokens.windows(3).find_map(|window| {
+        let [key, equals, value] = window else {
+            return None;
+        };
+
+        if key.kind() != "identifier" || key.utf8_text(source.as_bytes()).ok()? != "path" || equals.kind() != "=" {
+            return None;
+        }
+
+        let mut value_cursor = value.walk();
+        let content = value.named_children(&mut value_cursor).find(|x| x.kind() == "string_content")?;
+        Some(content.utf8_text(source.as_bytes()).ok()?.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parameters(snippets: &[&str]) -> Vec<String> {
+        snippet_parameters(&snippets.iter().map(|x| x.to_string()).collect::<Vec<_>>())
+    }
+
+    fn structs(source: &str) -> ComponentStructs {
+        let mut parser = Parser::new();
+        parser.set_language(&Language::new(tree_sitter_rust::LANGUAGE)).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+
+        let mut structs = ComponentStructs::new();
+        collect_structs(tree.root_node(), source, &[PathBuf::from("views")], &mut structs);
+        structs
+    }
+
+    #[test]
+    fn skips_primitive_types_and_paths() {
+        assert_eq!(parameters(&["(x as usize);", "(std::cmp::max(a, b));"]), ["x", "a", "b"]);
+    }
+
+    #[test]
+    fn skips_literals_and_lifetimes() {
+        assert_eq!(parameters(&["let c: &'static str = \"a b\"; (c == 'x' || flag);"]), ["flag"]);
+    }
+
+    #[test]
+    fn skips_bindings() {
+        let snippets = [
+            "for item in items {}",
+            "(item.name);",
+            "(values.iter().map(|value| value | mask).count());",
+            "if let Some(user) = current {}",
+            "match () { Some(x) => {} }",
+            "(user.id + x);",
+        ];
+
+        assert_eq!(parameters(&snippets), ["items", "values", "mask", "current"]);
+    }
+
+    #[test]
+    fn reads_macro_arguments_and_self_fields() {
+        assert_eq!(
+            parameters(&["(format!(\"{}\", title.len()));", "(self.count);"]),
+            ["title", "count"]
+        );
+    }
+
+    #[test]
+    fn finds_structs_with_rshtml_path() {
+        let source = r#"
+            #[derive(RsHtml)]
+            #[rshtml(path = "card.rs.html")]
+            struct Card {
+                title: String,
+                subtitle: Option<String>,
+            }
+
+            // the path of this one is a comment
+            #[derive(Debug)]
+            #[serde(rename = "rshtml")]
+            struct Other {
+                path: String,
+            }
+        "#;
+
+        let structs = structs(source);
+        assert_eq!(structs.len(), 1);
+        assert_eq!(
+            structs[Path::new("views/card.rs.html")],
+            [
+                ("title".to_string(), "String".to_string()),
+                ("subtitle".to_string(), "Option<String>".to_string())
+            ]
+        );
+    }
+}
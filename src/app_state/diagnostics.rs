@@ -0,0 +1,262 @@
+use crate::app_state::AppState;
+use crate::app_state::index::ViewSummary;
+use crate::app_state::view::View;
+use crate::app_state::workspace::Workspace;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, DiagnosticTag, NumberOrString, Url};
+
+pub const SYNTAX_ERROR: &str = "syntax-error";
+pub const UNRESOLVED_PATH: &str = "unresolved-path";
+pub const UNKNOWN_COMPONENT: &str = "unknown-component";
+pub const UNUSED_USE: &str = "unused-use";
+pub const SECTION_NOT_RENDERED: &str = "section-not-rendered";
+pub const SECTION_NOT_PROVIDED: &str = "section-not-provided";
+pub const DUPLICATE_SECTION: &str = "duplicate-section";
+pub const MISSING_PARAMETER: &str = "missing-parameter";
+
+pub type DiagnosticCache = HashMap<PathBuf, CachedDiagnostics>;
+
+// the workspace diagnostics last computed for a file, kept until the file or one it reads changes
+pub struct CachedDiagnostics {
+    pub diagnostics: Vec<Diagnostic>,
+    pub version: Option<i64>,
+    pub result_id: String,
+}
+
+impl AppState {
+    pub fn diagnostics(&self, uri: &Url, summary: &ViewSummary) -> Vec<Diagnostic> {
+        let mut diagnostics = summary.errors.clone();
+        for diagnostic in &mut diagnostics {
+            diagnostic.code = Some(NumberOrString::String(SYNTAX_ERROR.to_string()));
+        }
+
+        diagnostics.extend(self.unresolved_path_diagnostics(uri, summary));
+        diagnostics.extend(self.component_diagnostics(summary));
+        diagnostics.extend(self.parameter_diagnostics(uri, summary));
+        diagnostics.extend(self.section_diagnostics(uri, summary));
+
+        self.filter_diagnostics(diagnostics)
+    }
+
+    pub fn filter_diagnostics(&self, mut diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        if let Ok(settings) = self.settings.read() {
+            diagnostics.retain(|diagnostic| match &diagnostic.code {
+                Some(NumberOrString::String(code)) => settings.diagnostic_enabled(code),
+                _ => true,
+            });
+        }
+
+        diagnostics
+    }
+
+    fn unresolved_path_diagnostics(&self, uri: &Url, summary: &ViewSummary) -> Vec<Diagnostic> {
+        let Ok(file_path) = uri.to_file_path() else {
+            return Vec::new();
+        };
+
+        if self.view_roots(&file_path).is_empty() {
+            return Vec::new();
+        }
+
+        summary
+            .path_directives
+            .iter()
+            .filter_map(|(kind, path, range)| {
+                if self.resolve_directive_path(&file_path, kind, path.as_deref()).is_some() {
+                    return None;
+                }
+
+                let message = match path {
+                    Some(path) => format!("File `{path}` not found"),
+                    None => "Default layout not found".to_string(),
+                };
+
+                Some(Diagnostic {
+                    range: *range,
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    code: Some(NumberOrString::String(UNRESOLVED_PATH.to_string())),
+                    message,
+                    ..Default::default()
+                })
+            })
+            .collect()
+    }
+
+    fn component_diagnostics(&self, summary: &ViewSummary) -> Vec<Diagnostic> {
+        let use_names: HashSet<&str> = summary
+            .use_directives
+            .iter()
+            .filter_map(|(path, alias)| View::use_directive_name(path, alias.as_deref()))
+            .collect();
+        let components = &summary.components;
+
+        let unknown_components = components
+            .iter()
+            .filter(|(name, _)| !use_names.contains(name.as_str()))
+            .map(|(name, range)| Diagnostic {
+                range: *range,
+                severity: Some(DiagnosticSeverity::ERROR),
+                code: Some(NumberOrString::String(UNKNOWN_COMPONENT.to_string())),
+                message: format!("Unknown component `{name}`, no `use` directive imports it"),
+                ..Default::default()
+            });
+
+        let used_names: HashSet<&String> = components.iter().map(|(name, _)| name).collect();
+        let unused_uses = summary.use_locations.iter().filter_map(|(path, alias, range)| {
+            let name = View::use_directive_name(path, alias.as_deref())?.to_string();
+            if used_names.contains(&name) {
+                return None;
+            }
+
+            Some(Diagnostic {
+                range: *range,
+                severity: Some(DiagnosticSeverity::WARNING),
+                code: Some(NumberOrString::String(UNUSED_USE.to_string())),
+                message: format!("Component `{name}` is never used"),
+                tags: Some(vec![DiagnosticTag::UNNECESSARY]),
+                ..Default::default()
+            })
+        });
+
+        unknown_components.chain(unused_uses).collect()
+    }
+
+    fn parameter_diagnostics(&self, uri: &Url, summary: &ViewSummary) -> Vec<Diagnostic> {
+        summary
+            .component_tags
+            .iter()
+            .filter_map(|(name, parameters, range)| {
+                let component_path = self.find_view(uri, summary.use_directive_path(name)?)?;
+
+                let missing = self
+                    .component_parameters(&component_path)
+                    .into_iter()
+                    .filter(|(parameter, _, required)| *required && !parameters.contains(parameter))
+                    .map(|(parameter, _, _)| format!("`{parameter}`"))
+                    .collect::<Vec<_>>();
+
+                if missing.is_empty() {
+                    return None;
+                }
+
+                Some(Diagnostic {
+                    range: *range,
+                    severity: Some(DiagnosticSeverity::WARNING),
+                    code: Some(NumberOrString::String(MISSING_PARAMETER.to_string())),
+                    message: format!("Component `{name}` is missing parameters: {}", missing.join(", ")),
+                    ..Default::default()
+                })
+            })
+            .collect()
+    }
+
+    fn section_diagnostics(&self, uri: &Url, summary: &ViewSummary) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let sections = &summary.sections;
+
+        let mut seen = HashSet::new();
+        for (name, range) in sections {
+            if !seen.insert(name) {
+                diagnostics.push(Diagnostic {
+                    range: *range,
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    code: Some(NumberOrString::String(DUPLICATE_SECTION.to_string())),
+                    message: format!("Section `{name}` is already defined in this view"),
+                    ..Default::default()
+                });
+            }
+        }
+
+        let layout_path = summary
+            .extends
+            .as_ref()
+            .and_then(|extends| self.find_layout(uri, extends.as_deref()));
+
+        let layout_renders = layout_path.and_then(|layout_path| {
+            let index = self.index.read().ok()?;
+            let layout = index.get(&layout_path)?;
+            Some(layout.renders.iter().map(|(name, _)| name.clone()).collect::<HashSet<_>>())
+        });
+
+        if let Some(layout_renders) = layout_renders {
+            diagnostics.extend(
+                sections
+                    .iter()
+                    .filter(|(name, _)| !layout_renders.contains(name))
+                    .map(|(name, range)| Diagnostic {
+                        range: *range,
+                        severity: Some(DiagnosticSeverity::WARNING),
+                        code: Some(NumberOrString::String(SECTION_NOT_RENDERED.to_string())),
+                        message: format!("Section `{name}` is never rendered by the layout"),
+                        ..Default::default()
+                    }),
+            );
+        }
+
+        let renders = &summary.renders;
+        if !renders.is_empty()
+            && let Ok(file_path) = uri.to_file_path()
+        {
+            let provided: HashSet<String> = self
+                .find_child_views(&file_path)
+                .into_iter()
+                .flat_map(|(_, summary)| summary.section_names())
+                .collect();
+
+            diagnostics.extend(
+                renders
+                    .iter()
+                    .filter(|(name, _)| !provided.contains(name))
+                    .map(|(name, range)| Diagnostic {
+                        range: *range,
+                        severity: Some(DiagnosticSeverity::HINT),
+                        code: Some(NumberOrString::String(SECTION_NOT_PROVIDED.to_string())),
+                        message: format!("No view provides section `{name}`"),
+                        ..Default::default()
+                    }),
+            );
+        }
+
+        diagnostics
+    }
+
+    pub fn suggest_view_path(&self, uri: &Url, path: &str) -> Option<String> {
+        let file_path = uri.to_file_path().ok()?;
+
+        let mut candidates = Vec::new();
+        for root in self.view_roots(&file_path) {
+            let mut files = Vec::new();
+            Workspace::collect_view_files(&root, &mut files);
+
+            candidates.extend(files.iter().filter_map(|file| {
+                let relative = file.strip_prefix(&root).ok()?;
+                Some(relative.to_string_lossy().replace('\\', "/"))
+            }));
+        }
+
+        let max_distance = (path.len() / 3).max(3);
+        candidates
+            .into_iter()
+            .map(|candidate| (edit_distance(path, &candidate), candidate))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .min_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)))
+            .map(|(_, candidate)| candidate)
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b_chars = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b_chars.len()).collect::<Vec<_>>();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b_chars.len() + 1];
+        for (j, b_char) in b_chars.iter().enumerate() {
+            let cost = if a_char == *b_char { 0 } else { 1 };
+            current[j + 1] = (previous[j] + cost).min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b_chars.len()]
+}
@@ -0,0 +1,757 @@
+use crate::app_state::AppState;
+use std::io::Write;
+use std::ops::Range;
+use std::process::{Command, Stdio};
+use tower_lsp::lsp_types::{self, FormattingOptions, Position, TextEdit};
+use tracing::debug;
+use tree_sitter::{Node, Tree};
+
+pub type Edit = (Range<usize>, String);
+
+const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source", "track", "wbr",
+];
+
+const HTML_KINDS: [&str; 3] = ["html_text", "html_inner_text", "match_text"];
+const VERBATIM_KINDS: [&str; 4] = ["raw_content", "comment_block", "rust_expr_simple", "string_line"];
+const SPACING_KINDS: [&str; 4] = ["else_clause", "match_stmt_arm", "section_block", "as_clause"];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Event {
+    Open,
+    Close,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Region {
+    // kept byte for byte
+    Verbatim,
+    // moved to the current indentation, keeping its own relative indentation
+    Relative,
+    // rewritten as a whole by a rust snippet edit
+    Replaced,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum RustSnippetKind {
+    Block,
+    Expr,
+}
+
+enum Html {
+    Text,
+    Tag { name: Option<String>, quote: Option<u8> },
+    Comment(usize),
+    Content(String, Region, usize),
+}
+
+struct Formatter<'a> {
+    source: &'a str,
+    indent_unit: String,
+    options: &'a FormattingOptions,
+    range: Option<Range<usize>>,
+    events: Vec<(Range<usize>, Event)>,
+    regions: Vec<(Range<usize>, Region)>,
+    rust_snippets: Vec<(RustSnippetKind, Range<usize>)>,
+    line_indents: Vec<(usize, usize)>,
+    edits: Vec<Edit>,
+}
+
+pub fn format(source: &str, tree: &Tree, options: &FormattingOptions, range: Option<Range<usize>>) -> Vec<Edit> {
+    // whole lines around the requested range
+    let range = range.map(|range| {
+        let start = source[..range.start.min(source.len())].rfind('\n').map_or(0, |x| x + 1);
+        let end = source[range.end.min(source.len())..]
+            .find('\n')
+            .map_or(source.len(), |x| range.end + x);
+        start..end
+    });
+
+    let mut formatter = Formatter {
+        source,
+        indent_unit: if options.insert_spaces {
+            " ".repeat(options.tab_size as usize)
+        } else {
+            "\t".to_string()
+        },
+        options,
+        range: range.clone(),
+        events: Vec::new(),
+        regions: Vec::new(),
+        rust_snippets: Vec::new(),
+        line_indents: Vec::new(),
+        edits: Vec::new(),
+    };
+
+    let mut html_ranges = Vec::new();
+    formatter.visit(tree.root_node(), &mut html_ranges);
+    formatter.scan_html(&html_ranges);
+    formatter.events.sort_by_key(|(range, _)| range.start);
+
+    formatter.indent_lines();
+    formatter.format_rust();
+    formatter.final_newline();
+
+    let mut edits = formatter.edits;
+    if let Some(range) = range {
+        edits.retain(|(edit_range, _)| edit_range.start >= range.start && edit_range.end <= range.end);
+    }
+
+    edits.sort_by_key(|(edit_range, _)| edit_range.start);
+    edits
+}
+
+pub fn apply_edits(source: &str, edits: &[Edit]) -> String {
+    let mut formatted = source.to_string();
+    for (range, new_text) in edits.iter().rev() {
+        formatted.replace_range(range.clone(), new_text);
+    }
+
+    formatted
+}
+
+pub fn text_edits(source: &str, edits: Vec<Edit>) -> Vec<TextEdit> {
+    let line_starts: Vec<usize> = std::iter::once(0).chain(source.match_indices('\n').map(|(i, _)| i + 1)).collect();
+
+    let position = |offset: usize| {
+        let line = line_starts.partition_point(|start| *start <= offset) - 1;
+        Position {
+            line: line as u32,
+            character: source[line_starts[line]..offset].encode_utf16().count() as u32,
+        }
+    };
+
+    edits
+        .into_iter()
+        .map(|(range, new_text)| TextEdit {
+            range: lsp_types::Range {
+                start: position(range.start),
+                end: position(range.end),
+            },
+            new_text,
+        })
+        .collect()
+}
+
+impl AppState {
+    pub fn format_source(&self, source: &str, options: &FormattingOptions) -> Option<String> {
+        let tree = self.parser.lock().ok()?.parse(source, None)?;
+        let edits = format(source, &tree, options, None);
+
+        Some(apply_edits(source, &edits))
+    }
+}
+
+impl Formatter<'_> {
+    fn visit(&mut self, node: Node, html_ranges: &mut Vec<Range<usize>>) {
+        let kind = node.kind();
+
+        // nothing after the range changes its indentation
+        if self.range.as_ref().is_some_and(|range| node.start_byte() > range.end) {
+            return;
+        }
+
+        if HTML_KINDS.contains(&kind) {
+            html_ranges.push(node.byte_range());
+            return;
+        }
+
+        if VERBATIM_KINDS.contains(&kind) || node.is_error() {
+            self.regions.push((node.byte_range(), Region::Verbatim));
+            return;
+        }
+
+        match kind {
+            "rust_block" => {
+                if let Some(content) = self.inner_range(node, '{', '}') {
+                    self.regions.push((content.clone(), Region::Replaced));
+                    if self.contains(&content) {
+                        self.rust_snippets.push((RustSnippetKind::Block, content));
+                    }
+                }
+                return;
+            }
+            "rust_expr_paren" => {
+                if let Some(content) = self.inner_range(node, '(', ')')
+                    && self.contains(&content)
+                {
+                    self.rust_snippets.push((RustSnippetKind::Expr, content));
+                }
+                self.regions.push((node.byte_range(), Region::Verbatim));
+                return;
+            }
+            "open_brace" => self.events.push((node.end_byte()..node.end_byte(), Event::Open)),
+            "close_brace" => self.events.push((node.byte_range(), Event::Close)),
+            _ => {}
+        }
+
+        if let Some(head) = node.child_by_field_name("head").or_else(|| node.child_by_field_name("pattern")) {
+            self.regions.push((head.byte_range(), Region::Verbatim));
+        }
+
+        if (kind.ends_with("_directive") || kind.ends_with("_stmt") || SPACING_KINDS.contains(&kind)) && self.intersects(&node.byte_range())
+        {
+            self.normalize_spacing(node);
+        }
+
+        let mut cursor = node.walk();
+        let mut children_start = node.start_byte();
+
+        if kind == "component_tag" {
+            let tag_end = node
+                .children(&mut cursor)
+                .find(|child| matches!(child.kind(), "tag_close" | "tag_self_close"));
+
+            self.events.push((node.start_byte()..node.start_byte(), Event::Open));
+
+            if let Some(tag_end) = tag_end {
+                self.events.push((tag_end.byte_range(), Event::Close));
+
+                let closing_tag = self.source[tag_end.end_byte()..node.end_byte()].rfind("</");
+                if tag_end.kind() == "tag_close"
+                    && let Some(closing_tag) = closing_tag
+                {
+                    let closing_tag = tag_end.end_byte() + closing_tag;
+                    self.events.push((tag_end.end_byte()..tag_end.end_byte(), Event::Open));
+                    self.events.push((closing_tag..closing_tag + 2, Event::Close));
+                }
+
+                children_start = tag_end.end_byte();
+            }
+        }
+
+        let children = node.children(&mut cursor).collect::<Vec<_>>();
+        for child in children.into_iter().filter(|child| child.start_byte() >= children_start) {
+            self.visit(child, html_ranges);
+        }
+    }
+
+    fn contains(&self, range: &Range<usize>) -> bool {
+        self.range.as_ref().is_none_or(|x| x.start <= range.start && range.end <= x.end)
+    }
+
+    fn intersects(&self, range: &Range<usize>) -> bool {
+        self.range.as_ref().is_none_or(|x| x.start <= range.end && range.start <= x.end)
+    }
+
+    fn inner_range(&self, node: Node, open: char, close: char) -> Option<Range<usize>> {
+        let text = &self.source[node.byte_range()];
+        let start = text.find(open)? + 1;
+        let end = text.rfind(close)?;
+
+        (start <= end).then(|| node.start_byte() + start..node.start_byte() + end)
+    }
+
+    fn normalize_spacing(&mut self, node: Node) {
+        let is_directive = node.kind().ends_with("_directive");
+
+        let mut cursor = node.walk();
+        let mut children = Vec::new();
+        for child in node.children(&mut cursor) {
+            let is_block_start = child.kind() == "open_brace";
+            children.push(child);
+            if is_block_start {
+                break;
+            }
+        }
+
+        for pair in children.windows(2) {
+            let (left, right) = (pair[0].kind(), pair[1].kind());
+            let gap = pair[0].end_byte()..pair[1].start_byte();
+            let gap_text = &self.source[gap.clone()];
+
+            if !gap_text.chars().all(|c| c == ' ' || c == '\t') {
+                continue;
+            }
+
+            let spacing = if left == "start_symbol"
+                || left == "open_paren"
+                || right == "close_paren"
+                || right == ","
+                || (is_directive && right == "open_paren")
+            {
+                ""
+            } else if gap_text.is_empty() && right != "open_brace" && left != "," {
+                continue;
+            } else {
+                " "
+            };
+
+            if gap_text != spacing {
+                self.edits.push((gap, spacing.to_string()));
+            }
+        }
+    }
+
+    fn scan_html(&mut self, ranges: &[Range<usize>]) {
+        let bytes = self.source.as_bytes();
+        let mut state = Html::Text;
+
+        for range in ranges {
+            let mut i = range.start;
+
+            while i < range.end {
+                let rest = &bytes[i..range.end];
+
+                match &mut state {
+                    Html::Text if rest.starts_with(b"<!--") => {
+                        state = Html::Comment(i + 4);
+                        i += 4;
+                        continue;
+                    }
+                    Html::Text if rest[0] == b'<' => {
+                        let closing = rest.get(1) == Some(&b'/');
+                        let name_start = i + 1 + closing as usize;
+                        let name_len = bytes[name_start.min(range.end)..range.end]
+                            .iter()
+                            .take_while(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'!'))
+                            .count();
+
+                        if name_len > 0 {
+                            let name_end = name_start + name_len;
+                            let name = self.source[name_start..name_end].to_ascii_lowercase();
+
+                            if closing {
+                                self.events.push((i..name_end, Event::Close));
+                                state = Html::Tag { name: None, quote: None };
+                            } else {
+                                self.events.push((i..i, Event::Open));
+                                state = Html::Tag {
+                                    name: Some(name),
+                                    quote: None,
+                                };
+                            }
+
+                            i = name_end;
+                            continue;
+                        }
+                    }
+                    Html::Text => {}
+                    Html::Tag { quote, .. } if quote.is_some() => {
+                        if *quote == Some(rest[0]) {
+                            *quote = None;
+                        }
+                    }
+                    Html::Tag { quote, .. } if matches!(rest[0], b'"' | b'\'') => *quote = Some(rest[0]),
+                    Html::Tag { name, .. } if rest[0] == b'>' => {
+                        let mut next = Html::Text;
+
+                        if let Some(name) = name.take() {
+                            self.events.push((i..i + 1, Event::Close));
+
+                            let self_closing = i > 0 && bytes[i - 1] == b'/';
+                            if !self_closing && !name.starts_with('!') && !VOID_ELEMENTS.contains(&name.as_str()) {
+                                self.events.push((i + 1..i + 1, Event::Open));
+
+                                match name.as_str() {
+                                    "pre" | "textarea" => next = Html::Content(name, Region::Verbatim, i + 1),
+                                    "script" | "style" => next = Html::Content(name, Region::Relative, i + 1),
+                                    _ => {}
+                                }
+                            }
+                        }
+
+                        state = next;
+                    }
+                    Html::Tag { .. } => {}
+                    Html::Comment(start) => {
+                        if rest.starts_with(b"-->") {
+                            self.regions.push((*start..i, Region::Relative));
+                            state = Html::Text;
+                            i += 3;
+                            continue;
+                        }
+                    }
+                    Html::Content(name, region, start) => {
+                        let closing_tag = format!("</{name}");
+                        let end = self.source[i..range.end].to_ascii_lowercase().find(&closing_tag);
+
+                        match end {
+                            Some(end) => {
+                                self.regions.push((*start..i + end, *region));
+                                state = Html::Text;
+                                i += end;
+                            }
+                            None => i = range.end,
+                        }
+                        continue;
+                    }
+                }
+
+                i += 1;
+            }
+        }
+    }
+
+    fn region_at(&self, line_start: usize) -> Option<(usize, Range<usize>, Region)> {
+        self.regions
+            .iter()
+            .enumerate()
+            .filter(|(_, (range, _))| range.start < line_start && line_start <= range.end)
+            .min_by_key(|(_, (range, _))| range.len())
+            .map(|(i, (range, region))| (i, range.clone(), *region))
+    }
+
+    fn indent_lines(&mut self) {
+        let source = self.source;
+        let mut depth = 0usize;
+        let mut next_event = 0;
+        let mut relative_bases: Vec<Option<usize>> = vec![None; self.regions.len()];
+        let mut line_start = 0;
+
+        for line in source.split_inclusive('\n') {
+            if self.range.as_ref().is_some_and(|range| line_start > range.end) {
+                break;
+            }
+
+            let line_end = line_start + line.len();
+            let content_end = line_start + line.trim_end_matches(['\n', '\r']).len();
+            let text = &source[line_start..content_end];
+            let first = line_start + (text.len() - text.trim_start().len());
+            let last = line_start + text.trim_end().len();
+            let blank = text.trim().is_empty();
+
+            let mut indent = depth;
+            let mut position = first;
+            let is_last_line = line_end == source.len();
+
+            while next_event < self.events.len()
+                && (self.events[next_event].0.start < line_end || is_last_line && self.events[next_event].0.start == line_end)
+            {
+                let (range, event) = self.events[next_event].clone();
+                match event {
+                    Event::Open => depth += 1,
+                    Event::Close => {
+                        depth = depth.saturating_sub(1);
+                        if range.start == position {
+                            indent = indent.saturating_sub(1);
+                            position = range.end + source[range.end..content_end].len() - source[range.end..content_end].trim_start().len();
+                        }
+                    }
+                }
+                next_event += 1;
+            }
+
+            self.line_indents.push((line_start, indent));
+
+            let region = self.region_at(line_start);
+            let leading = match region {
+                Some((_, _, Region::Verbatim | Region::Replaced)) => None,
+                Some((i, range, Region::Relative)) if first != range.end => {
+                    if blank {
+                        Some(String::new())
+                    } else {
+                        let base = *relative_bases[i].get_or_insert_with(|| self.relative_base(&range));
+                        let own = &source[line_start..first];
+                        Some(self.indent(indent) + own.get(base..).unwrap_or(""))
+                    }
+                }
+                _ if blank => Some(String::new()),
+                _ => Some(self.indent(indent)),
+            };
+
+            if let Some(leading) = leading
+                && source[line_start..first] != leading
+            {
+                self.edits.push((line_start..first, leading));
+            }
+
+            let trailing = last..content_end;
+            if !trailing.is_empty()
+                && !blank
+                && !self
+                    .regions
+                    .iter()
+                    .any(|(range, _)| range.start < trailing.end && trailing.start < range.end)
+            {
+                self.edits.push((trailing, String::new()));
+            }
+
+            line_start = line_end;
+        }
+    }
+
+    fn relative_base(&self, range: &Range<usize>) -> usize {
+        self.source[range.clone()]
+            .split('\n')
+            .skip(1)
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or(0)
+    }
+
+    fn indent(&self, depth: usize) -> String {
+        self.indent_unit.repeat(depth)
+    }
+
+    fn line_indent(&self, offset: usize) -> usize {
+        let line = self.line_indents.partition_point(|(start, _)| *start <= offset);
+        line.checked_sub(1).map_or(0, |line| self.line_indents[line].1)
+    }
+
+    fn format_rust(&mut self) {
+        let snippets = self
+            .rust_snippets
+            .iter()
+            .map(|(_, range)| &self.source[range.clone()])
+            .collect::<Vec<_>>();
+
+        let formatted = rustfmt(&snippets, self.options);
+
+        for ((kind, range), formatted) in self.rust_snippets.clone().into_iter().zip(formatted) {
+            let original = &self.source[range.clone()];
+            if original.trim().is_empty() {
+                continue;
+            }
+
+            let new_text = match kind {
+                RustSnippetKind::Expr => match formatted.as_deref() {
+                    Some([line]) if !original.contains('\n') => line.clone(),
+                    _ => continue,
+                },
+                RustSnippetKind::Block => {
+                    let lines = formatted.unwrap_or_else(|| dedent(original));
+                    let depth = self.line_indent(range.start);
+
+                    match lines.as_slice() {
+                        [line] if !original.contains('\n') => format!(" {line} "),
+                        _ => {
+                            let body = lines
+                                .iter()
+                                .map(|line| match line.is_empty() {
+                                    true => String::new(),
+                                    false => self.indent(depth + 1) + line,
+                                })
+                                .collect::<Vec<_>>()
+                                .join("\n");
+
+                            format!("\n{body}\n{}", self.indent(depth))
+                        }
+                    }
+                }
+            };
+
+            if new_text != original {
+                self.edits.push((range, new_text));
+            }
+        }
+    }
+
+    fn final_newline(&mut self) {
+        let content_end = self.source.trim_end().len();
+        let ending = &self.source[content_end..];
+        if content_end == 0 || self.region_at(self.source.len()).is_some() {
+            return;
+        }
+
+        let trim = self.options.trim_final_newlines == Some(true) && ending.matches('\n').count() > 1;
+        let insert = self.options.insert_final_newline == Some(true) && !ending.contains('\n');
+
+        if trim || insert {
+            self.edits.retain(|(range, _)| range.start < content_end);
+            self.edits.push((content_end..self.source.len(), "\n".to_string()));
+        }
+    }
+}
+
+fn dedent(source: &str) -> Vec<String> {
+    // the indentation before the closing brace is a line of its own, it isn't part of the body
+    let mut lines = source
+        .lines()
+        .map(str::trim_end)
+        .skip_while(|line| line.is_empty())
+        .collect::<Vec<_>>();
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+
+    let base = lines
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    lines.iter().map(|line| line.get(base..).unwrap_or("").to_string()).collect()
+}
+
+fn rustfmt(snippets: &[&str], options: &FormattingOptions) -> Vec<Option<Vec<String>>> {
+    if snippets.is_empty() {
+        return Vec::new();
+    }
+
+    let wrap = |i: usize, snippet: &str| format!("fn __rshtml_{i}() {{\n{snippet}\n}}\n");
+
+    let source = snippets.iter().enumerate().map(|(i, snippet)| wrap(i, snippet)).collect::<String>();
+    match run_rustfmt(&source, options) {
+        Ok(Some(output)) => {
+            let bodies = unwrap_bodies(&output, options);
+            if bodies.len() == snippets.len() {
+                return bodies.into_iter().map(Some).collect();
+            }
+        }
+        Ok(None) => {}
+        Err(e) => {
+            debug!("rustfmt couldn't run: {}", e);
+            return vec![None; snippets.len()];
+        }
+    }
+
+    // one snippet that isn't valid rust shouldn't keep the others unformatted
+    snippets
+        .iter()
+        .map(|snippet| {
+            let output = run_rustfmt(&wrap(0, snippet), options).ok()??;
+            unwrap_bodies(&output, options).pop()
+        })
+        .collect()
+}
+
+fn run_rustfmt(source: &str, options: &FormattingOptions) -> std::io::Result<Option<String>> {
+    let config = format!("tab_spaces={},hard_tabs={}", options.tab_size, !options.insert_spaces);
+
+    let mut child = Command::new("rustfmt")
+        .args(["--edition", "2024", "--config", &config])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(source.as_bytes())?;
+    }
+
+    let output = child.wait_with_output()?;
+    Ok(output.status.success().then(|| String::from_utf8_lossy(&output.stdout).to_string()))
+}
+
+fn unwrap_bodies(output: &str, options: &FormattingOptions) -> Vec<Vec<String>> {
+    let indent_unit = if options.insert_spaces {
+        " ".repeat(options.tab_size as usize)
+    } else {
+        "\t".to_string()
+    };
+
+    let mut bodies: Vec<Vec<String>> = Vec::new();
+    let mut in_body = false;
+
+    for line in output.lines() {
+        if line.starts_with("fn __rshtml_") {
+            bodies.push(Vec::new());
+            in_body = !line.ends_with("{}");
+        } else if line == "}" {
+            in_body = false;
+        } else if in_body && let Some(body) = bodies.last_mut() {
+            body.push(line.strip_prefix(&indent_unit).unwrap_or(line).to_string());
+        }
+    }
+
+    bodies
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tree_sitter::{Language, Parser};
+
+    fn format_text(source: &str) -> String {
+        format_range(source, None)
+    }
+
+    fn format_range(source: &str, range: Option<Range<usize>>) -> String {
+        let mut parser = Parser::new();
+        parser.set_language(&Language::new(tree_sitter_rshtml::LANGUAGE)).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+
+        let options = FormattingOptions {
+            tab_size: 4,
+            insert_spaces: true,
+            insert_final_newline: Some(true),
+            ..Default::default()
+        };
+
+        apply_edits(source, &format(source, &tree, &options, range))
+    }
+
+    // the rust code is formatted by the rustfmt on the path, tests that need it are skipped without one
+    fn rustfmt_installed() -> bool {
+        Command::new("rustfmt")
+            .arg("--version")
+            .output()
+            .is_ok_and(|output| output.status.success())
+    }
+
+    #[test]
+    fn indents_if_block() {
+        let source = "@if visible {\n<p>yes</p>\n} else {\n<p>no</p>\n}\n";
+        let expected = "@if visible {\n    <p>yes</p>\n} else {\n    <p>no</p>\n}\n";
+
+        assert_eq!(format_text(source), expected);
+    }
+
+    #[test]
+    fn indents_nested_for_block() {
+        let source = "<ul>\n@for item in items {\n<li>@item</li>\n}\n</ul>\n";
+        let expected = "<ul>\n    @for item in items {\n        <li>@item</li>\n    }\n</ul>\n";
+
+        assert_eq!(format_text(source), expected);
+    }
+
+    #[test]
+    fn keeps_pre_content() {
+        let source = "<div>\n<pre>\n  keep\n      this\n</pre>\n</div>\n";
+        let expected = "<div>\n    <pre>\n  keep\n      this\n</pre>\n</div>\n";
+
+        assert_eq!(format_text(source), expected);
+    }
+
+    #[test]
+    fn keeps_raw_content() {
+        let source = "<div>\n@raw {\n<b>  x </b>\n   @y\n    }\n</div>\n";
+        let expected = "<div>\n    @raw {\n<b>  x </b>\n   @y\n    }\n</div>\n";
+
+        assert_eq!(format_text(source), expected);
+    }
+
+    #[test]
+    fn inserts_final_newline() {
+        assert_eq!(format_text("<p>x</p>"), "<p>x</p>\n");
+    }
+
+    #[test]
+    fn formats_only_the_requested_lines() {
+        let source = "@if visible {\n<p>x</p>\n}\n<div>\n<p>y</p>\n</div>";
+        let expected = "@if visible {\n    <p>x</p>\n}\n<div>\n<p>y</p>\n</div>";
+
+        assert_eq!(format_range(source, Some(16..17)), expected);
+    }
+
+    #[test]
+    fn dedents_rust_blocks_without_rustfmt() {
+        let source = "\n        let x = 1;\n\n        if x > 0 {\n            run(x);\n        }   \n    ";
+
+        assert_eq!(dedent(source), ["let x = 1;", "", "if x > 0 {", "    run(x);", "}"]);
+    }
+
+    #[test]
+    fn indents_rust_block() {
+        // already formatted code, so rustfmt and the dedent fallback give the same lines
+        let source = "<div>\n@{\n            let x = 1;\n}\n</div>\n";
+        let expected = "<div>\n    @{\n        let x = 1;\n    }\n</div>\n";
+
+        assert_eq!(format_text(source), expected);
+    }
+
+    #[test]
+    fn formats_rust_block_with_rustfmt() {
+        if !rustfmt_installed() {
+            return;
+        }
+
+        let source = "@{\nlet x=vec![1,2];\n}\n";
+        let expected = "@{\n    let x = vec![1, 2];\n}\n";
+
+        assert_eq!(format_text(source), expected);
+    }
+}
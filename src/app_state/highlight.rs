@@ -2,7 +2,7 @@ use crate::backend::semantic_tokens_builder::SemanticTokensBuilder;
 use crate::consts::{SEMANTIC_TOKEN_MODIFIERS, SEMANTIC_TOKEN_TYPES};
 use std::collections::HashMap;
 use std::ops::Range;
-use std::sync::Mutex;
+use std::sync::{Mutex, RwLock};
 use tower_lsp::jsonrpc::{Error, ErrorCode};
 use tower_lsp::lsp_types::{SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokensEdit};
 use tracing::error;
@@ -14,6 +14,7 @@ pub struct Highlight {
     pub highlight_injects: HashMap<&'static str, HighlightConfiguration>,
     pub highlight_names: Vec<String>,
     pub token_type_map: HashMap<SemanticTokenType, u32>,
+    pub token_type_overrides: RwLock<HashMap<String, SemanticTokenType>>,
     //pub token_modifier_map: HashMap<SemanticTokenModifier, u32>,
 }
 
@@ -25,6 +26,7 @@ impl Highlight {
             highlight_names,
             highlight_injects: HashMap::new(),
             token_type_map: Self::build_token_type_map(),
+            token_type_overrides: RwLock::new(HashMap::new()),
             //token_modifier_map: Self::build_token_modifier_map(),
         }
     }
@@ -37,6 +39,24 @@ impl Highlight {
             .collect()
     }
 
+    pub fn set_token_type_overrides(&self, overrides: &HashMap<String, String>) {
+        let overrides = overrides
+            .iter()
+            .filter_map(|(capture_name, token_type_name)| {
+                let token_type = SEMANTIC_TOKEN_TYPES.into_iter().find(|x| x.as_str() == token_type_name);
+                if token_type.is_none() {
+                    error!("Unknown semantic token type `{}` for `{}`", token_type_name, capture_name);
+                }
+
+                Some((capture_name.clone(), token_type?))
+            })
+            .collect();
+
+        if let Ok(mut token_type_overrides) = self.token_type_overrides.write() {
+            *token_type_overrides = overrides;
+        }
+    }
+
     #[allow(dead_code)]
     fn build_token_modifier_map() -> HashMap<SemanticTokenModifier, u32> {
         SEMANTIC_TOKEN_MODIFIERS
@@ -79,10 +99,10 @@ impl Highlight {
             if let Ok(highlight_event) = highlight_event {
                 match highlight_event {
                     HighlightEvent::Source { start, end } => {
-                        if let Some(ref r) = range {
-                            if start >= r.end || end <= r.start {
-                                continue;
-                            }
+                        if let Some(ref r) = range
+                            && (start >= r.end || end <= r.start)
+                        {
+                            continue;
                         }
 
                         if let Some(highlight_id) = highlight_stack.last() {
@@ -127,6 +147,14 @@ impl Highlight {
         let highlight_name = &self.highlight_names[highlight_id.0].as_str();
         let base_name = highlight_name.split('.').next().unwrap_or(highlight_name);
 
+        if let Ok(token_type_overrides) = self.token_type_overrides.read()
+            && let Some(token_type) = token_type_overrides
+                .get(*highlight_name)
+                .or_else(|| token_type_overrides.get(base_name))
+        {
+            return self.token_type_map.get(token_type).copied().unwrap_or(1);
+        }
+
         let lsp_type_name = match base_name {
             "keyword" => SemanticTokenType::KEYWORD,
             "comment" => SemanticTokenType::COMMENT,
@@ -167,11 +195,7 @@ impl Highlight {
 
     #[allow(dead_code)]
     pub fn semantic_tokens_difference(&self, old_tokens: &[SemanticToken], new_tokens: &[SemanticToken]) -> Vec<SemanticTokensEdit> {
-        let common_prefix_len = old_tokens
-            .iter()
-            .zip(new_tokens.iter())
-            .take_while(|(old, new)| old == new)
-            .count();
+        let common_prefix_len = old_tokens.iter().zip(new_tokens.iter()).take_while(|(old, new)| old == new).count();
 
         if common_prefix_len == old_tokens.len() && common_prefix_len == new_tokens.len() {
             return vec![];
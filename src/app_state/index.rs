@@ -0,0 +1,78 @@
+use crate::app_state::component::template_parameters;
+use crate::app_state::view::View;
+use crate::backend::tree_extensions::TreeExtensions;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+use tower_lsp::lsp_types::{Diagnostic, Range};
+use tree_sitter::{Language, Parser, Tree};
+
+pub type ViewIndex = HashMap<PathBuf, ViewSummary>;
+
+#[derive(Clone, Debug, Default)]
+pub struct ViewSummary {
+    pub include_paths: Vec<String>,
+    pub use_directives: Vec<(String, Option<String>)>,
+    pub use_locations: Vec<(String, Option<String>, Range)>,
+    pub extends: Option<Option<String>>,
+    pub sections: Vec<(String, Range)>,
+    pub renders: Vec<(String, Range)>,
+    pub components: Vec<(String, Range)>,
+    pub component_tags: Vec<(String, Vec<String>, Range)>,
+    pub parameters: Vec<String>,
+    pub path_directives: Vec<(&'static str, Option<String>, Range)>,
+    pub errors: Vec<Diagnostic>,
+}
+
+impl ViewSummary {
+    pub fn new(tree: &Tree, language: &Language, source: &str) -> Self {
+        Self {
+            include_paths: tree.find_includes(language, source),
+            use_directives: tree.find_uses(language, source),
+            use_locations: tree.find_use_locations(language, source),
+            extends: tree.find_extends(language, source),
+            sections: tree.find_sections(language, source),
+            renders: tree.find_renders(language, source),
+            components: tree.find_components(language, source),
+            component_tags: tree.find_component_tags(language, source),
+            parameters: template_parameters(tree, source),
+            path_directives: tree.find_path_directives(language, source),
+            errors: tree.find_error(language, source),
+        }
+    }
+
+    pub fn from_file(path: &Path, parser: &mut Parser, language: &Language) -> io::Result<Self> {
+        let source = fs::read_to_string(path)?;
+        let tree = parser
+            .parse(&source, None)
+            .ok_or_else(|| io::Error::other("Parser error: Couldn't create tree."))?;
+
+        Ok(Self::new(&tree, language, &source))
+    }
+
+    pub fn use_directive_path(&self, use_name: &str) -> Option<&str> {
+        self.use_directives
+            .iter()
+            .find(|(path, name)| View::use_directive_name(path, name.as_deref()) == Some(use_name))
+            .map(|(path, _)| path.as_str())
+    }
+
+    pub fn section_names(&self) -> Vec<String> {
+        self.sections.iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    pub fn index_files(files: &[PathBuf], language: &Language) -> ViewIndex {
+        let mut parser = Parser::new();
+        if parser.set_language(language).is_err() {
+            return ViewIndex::new();
+        }
+
+        files
+            .iter()
+            .filter_map(|path| {
+                let summary = Self::from_file(path, &mut parser, language).ok()?;
+                Some((path.clone(), summary))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,282 @@
+use std::ops::Range;
+use std::path::Path;
+use toml::Spanned;
+use toml::de::{DeTable, DeValue};
+use tower_lsp::lsp_types::{self, Diagnostic, DiagnosticSeverity, NumberOrString, Position};
+
+pub const INVALID_CONFIG: &str = "invalid-config";
+pub const DEFAULT_LAYOUT: &str = "layout.rs.html";
+
+pub struct RshtmlConfig {
+    pub views_paths: Vec<String>,
+    pub views_layout: Option<String>,
+    pub include_paths: Vec<String>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Default for RshtmlConfig {
+    fn default() -> Self {
+        Self {
+            views_paths: vec!["views".to_string()],
+            views_layout: None,
+            include_paths: Vec::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+}
+
+type Value<'a> = Spanned<DeValue<'a>>;
+
+struct ConfigParser<'a> {
+    member_path: &'a Path,
+    problems: Vec<(Range<usize>, DiagnosticSeverity, String)>,
+}
+
+impl RshtmlConfig {
+    pub fn parse(member_path: &Path, source: &str) -> Self {
+        let mut config = Self::default();
+
+        let (document, _) = DeTable::parse_recoverable(source);
+        let document = DeValue::Table(document.into_inner());
+        let Some(rshtml) = document
+            .get("package")
+            .and_then(|package| package.get_ref().get("metadata"))
+            .and_then(|metadata| metadata.get_ref().get("rshtml"))
+        else {
+            return config;
+        };
+
+        let mut parser = ConfigParser {
+            member_path,
+            problems: Vec::new(),
+        };
+
+        if parser.table(rshtml, "package.metadata.rshtml") {
+            let rshtml = rshtml.get_ref();
+
+            if let Some(views) = rshtml.get("views")
+                && parser.table(views, "views")
+            {
+                if let Some(path) = views.get_ref().get("path") {
+                    let views_paths = parser.directories(path, "views.path");
+                    if !views_paths.is_empty() {
+                        config.views_paths = views_paths;
+                    }
+                }
+
+                if let Some(layout) = views
+                    .get_ref()
+                    .get("layout")
+                    .and_then(|layout| parser.string(layout, "views.layout"))
+                {
+                    config.views_layout = Some(layout);
+                }
+            }
+
+            if let Some(include_dirs) = rshtml.get("include_dirs") {
+                config.include_paths = parser.directories(include_dirs, "include_dirs");
+            }
+        }
+
+        config.diagnostics = parser
+            .problems
+            .into_iter()
+            .map(|(span, severity, message)| Diagnostic {
+                range: span_to_range(source, span),
+                severity: Some(severity),
+                code: Some(NumberOrString::String(INVALID_CONFIG.to_string())),
+                message,
+                ..Default::default()
+            })
+            .collect();
+
+        config
+    }
+}
+
+impl ConfigParser<'_> {
+    fn table(&mut self, value: &Value, key: &str) -> bool {
+        let is_table = value.get_ref().is_table();
+        if !is_table {
+            self.invalid_type(value, key, "a table");
+        }
+
+        is_table
+    }
+
+    fn string(&mut self, value: &Value, key: &str) -> Option<String> {
+        match value.get_ref().as_str() {
+            Some(string) if !string.trim().is_empty() => Some(string.to_string()),
+            Some(_) => {
+                self.problems
+                    .push((value.span(), DiagnosticSeverity::ERROR, format!("`{key}` must not be empty")));
+                None
+            }
+            None => {
+                self.invalid_type(value, key, "a string");
+                None
+            }
+        }
+    }
+
+    fn directories(&mut self, value: &Value, key: &str) -> Vec<String> {
+        let values = match value.get_ref().as_array() {
+            Some(array) => array.iter().collect::<Vec<_>>(),
+            None if value.get_ref().is_str() => vec![value],
+            None => {
+                self.invalid_type(value, key, "a string or an array of strings");
+                return Vec::new();
+            }
+        };
+
+        if values.is_empty() {
+            self.problems
+                .push((value.span(), DiagnosticSeverity::ERROR, format!("`{key}` must not be empty")));
+        }
+
+        values
+            .into_iter()
+            .filter_map(|value| {
+                let path = self.string(value, key)?;
+                if !self.member_path.join(&path).is_dir() {
+                    self.problems.push((
+                        value.span(),
+                        DiagnosticSeverity::WARNING,
+                        format!("Directory `{path}` doesn't exist"),
+                    ));
+                }
+
+                Some(path)
+            })
+            .collect()
+    }
+
+    fn invalid_type(&mut self, value: &Value, key: &str, expected: &str) {
+        let message = format!("`{key}` must be {expected}, found {}", value.get_ref().type_str());
+        self.problems.push((value.span(), DiagnosticSeverity::ERROR, message));
+    }
+}
+
+fn span_to_range(source: &str, span: Range<usize>) -> lsp_types::Range {
+    lsp_types::Range {
+        start: offset_to_position(source, span.start),
+        end: offset_to_position(source, span.end),
+    }
+}
+
+fn offset_to_position(source: &str, offset: usize) -> Position {
+    let before = &source[..offset.min(source.len())];
+    let line_start = before.rfind('\n').map_or(0, |x| x + 1);
+
+    Position {
+        line: before.matches('\n').count() as u32,
+        character: before[line_start..].encode_utf16().count() as u32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::temp_files;
+
+    fn messages(config: &RshtmlConfig) -> Vec<(u32, Option<DiagnosticSeverity>, &str)> {
+        config
+            .diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.range.start.line, diagnostic.severity, diagnostic.message.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn reads_nested_tables() {
+        let dir = temp_files(&[("pages/", ""), ("admin/", ""), ("shared/", "")]);
+        let root = dir.path();
+
+        let config = RshtmlConfig::parse(
+            root,
+            r#"
+[package]
+name = "app"
+
+[package.metadata.rshtml]
+include_dirs = "shared"
+
+[package.metadata.rshtml.views]
+path = ["pages", "admin"]
+layout = "base.rs.html"
+"#,
+        );
+        assert_eq!(config.views_paths, ["pages", "admin"]);
+        assert_eq!(config.views_layout.as_deref(), Some("base.rs.html"));
+        assert_eq!(config.include_paths, ["shared"]);
+        assert!(config.diagnostics.is_empty());
+
+        let config = RshtmlConfig::parse(root, "[package]\nname = \"app\"\nmetadata.rshtml.views = { path = \"pages\" }\n");
+        assert_eq!(config.views_paths, ["pages"]);
+        assert!(config.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn keeps_defaults_without_metadata() {
+        let config = RshtmlConfig::parse(Path::new("/nonexistent"), "[package]\nname = \"app\"\n");
+
+        assert_eq!(config.views_paths, ["views"]);
+        assert_eq!(config.views_layout, None);
+        assert!(config.include_paths.is_empty());
+        assert!(config.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn reports_invalid_values() {
+        let config = RshtmlConfig::parse(
+            Path::new("/nonexistent"),
+            r#"[package.metadata.rshtml]
+include_dirs = 3
+
+[package.metadata.rshtml.views]
+path = []
+layout = " "
+"#,
+        );
+
+        assert_eq!(config.views_paths, ["views"]);
+        assert_eq!(config.views_layout, None);
+        assert!(config.include_paths.is_empty());
+        assert_eq!(
+            messages(&config),
+            [
+                (4, Some(DiagnosticSeverity::ERROR), "`views.path` must not be empty"),
+                (5, Some(DiagnosticSeverity::ERROR), "`views.layout` must not be empty"),
+                (
+                    1,
+                    Some(DiagnosticSeverity::ERROR),
+                    "`include_dirs` must be a string or an array of strings, found integer"
+                ),
+            ]
+        );
+        assert!(
+            config
+                .diagnostics
+                .iter()
+                .all(|diagnostic| diagnostic.code == Some(NumberOrString::String(INVALID_CONFIG.to_string())))
+        );
+    }
+
+    #[test]
+    fn reports_wrong_tables_and_missing_directories() {
+        let config = RshtmlConfig::parse(
+            Path::new("/nonexistent"),
+            "[package.metadata.rshtml]\nviews = \"pages\"\ninclude_dirs = [\"shared\", 1]\n",
+        );
+
+        assert_eq!(config.include_paths, ["shared"]);
+        assert_eq!(
+            messages(&config),
+            [
+                (1, Some(DiagnosticSeverity::ERROR), "`views` must be a table, found string"),
+                (2, Some(DiagnosticSeverity::WARNING), "Directory `shared` doesn't exist"),
+                (2, Some(DiagnosticSeverity::ERROR), "`include_dirs` must be a string, found integer"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,40 @@
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use tracing::error;
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Settings {
+    pub diagnostics: HashMap<String, bool>,
+    pub extra_view_dirs: Vec<String>,
+    pub default_layout: Option<String>,
+    pub snippet_style: SnippetStyle,
+    pub log_level: Option<String>,
+    pub log_file: Option<String>,
+    pub semantic_tokens: HashMap<String, String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SnippetStyle {
+    #[default]
+    Snippet,
+    Plain,
+}
+
+impl Settings {
+    pub fn from_value(value: Value) -> Option<Self> {
+        // editors either send the settings object itself or wrap it in its `rshtml` section
+        let value = match value.get("rshtml") {
+            Some(section) => section.clone(),
+            None => value,
+        };
+
+        serde_json::from_value(value).map_err(|e| error!("Invalid settings: {}", e)).ok()
+    }
+
+    pub fn diagnostic_enabled(&self, code: &str) -> bool {
+        self.diagnostics.get(code).copied().unwrap_or(true)
+    }
+}
@@ -33,31 +33,35 @@ impl View {
         }
     }
 
+    pub fn use_directive_name<'a>(path: &'a str, alias: Option<&'a str>) -> Option<&'a str> {
+        let name_str = alias
+            .or_else(|| path.trim_end_matches(".rs.html").split('/').next_back())
+            .unwrap_or("");
+
+        if name_str.is_empty() { None } else { Some(name_str) }
+    }
+
     pub fn use_directives_names(&self) -> Vec<String> {
         self.use_directives
             .iter()
-            .filter_map(|(path, name)| {
-                let name_str = name
-                    .as_deref()
-                    .or_else(|| path.trim_end_matches(".rs.html").split('/').next_back())
-                    .unwrap_or("");
-
-                if name_str.is_empty() {
-                    None
-                } else {
-                    Some(name_str.to_string())
-                }
-            })
+            .filter_map(|(path, name)| Self::use_directive_name(path, name.as_deref()).map(|name| name.to_string()))
             .collect()
     }
 
+    pub fn use_directive_path(&self, use_name: &str) -> Option<&str> {
+        self.use_directives
+            .iter()
+            .find(|(path, name)| Self::use_directive_name(path, name.as_deref()) == Some(use_name))
+            .map(|(path, _)| path.as_str())
+    }
+
     fn use_directive_completion_item(use_name: &str) -> (char, CompletionItem) {
         let tag_item = CompletionItem {
             label: use_name.to_owned(),
             kind: Some(CompletionItemKind::STRUCT),
             detail: Some(format!("{use_name} component")),
             insert_text_format: Some(InsertTextFormat::SNIPPET),
-            insert_text: Some(use_name.to_owned() + " ${1:parameters} />"),
+            insert_text: Some(use_name.to_owned() + " $0/>"),
             sort_text: Some("01".to_string()),
             ..Default::default()
         };
@@ -78,8 +82,7 @@ impl View {
     pub fn update_use_directive_completion_items(&mut self) {
         let current_names: HashSet<String> = self.use_directives_names().into_iter().collect();
 
-        self.completion_items
-            .retain(|name, _| current_names.contains(name));
+        self.completion_items.retain(|name, _| current_names.contains(name));
 
         for name in current_names {
             self.completion_items
@@ -106,8 +109,7 @@ impl View {
         let section_names = &self.section_names;
         for section_name in section_names {
             let item = Self::section_completion_item(section_name);
-            self.completion_items
-                .insert(format!("section_{}", section_name.to_owned()), item);
+            self.completion_items.insert(format!("section_{}", section_name.to_owned()), item);
         }
     }
 }
@@ -1,7 +1,10 @@
+use crate::app_state::manifest::{DEFAULT_LAYOUT, RshtmlConfig};
+use glob::MatchOptions;
 use std::fs;
 use std::path::{Path, PathBuf};
 use toml::Value;
-use tracing::debug;
+use tower_lsp::lsp_types::Diagnostic;
+use tracing::{debug, error};
 
 pub struct Workspace {
     pub root: PathBuf,
@@ -10,8 +13,29 @@ pub struct Workspace {
 
 pub struct Member {
     pub path: PathBuf,
-    pub views_path: PathBuf,
-    pub views_layout: String,
+    pub views_paths: Vec<PathBuf>,
+    pub include_paths: Vec<PathBuf>,
+    pub views_layout: Option<String>,
+    pub manifest_diagnostics: Vec<Diagnostic>,
+}
+
+impl Member {
+    pub fn view_roots(&self) -> Vec<PathBuf> {
+        self.views_paths.iter().chain(&self.include_paths).cloned().collect()
+    }
+
+    pub fn views_file(&self, path: &str) -> Option<PathBuf> {
+        views_file(&self.views_paths, path)
+    }
+}
+
+// the first views directory that has the file, or the first one when none has it yet
+pub fn views_file(views_paths: &[PathBuf], path: &str) -> Option<PathBuf> {
+    views_paths
+        .iter()
+        .map(|views_path| views_path.join(path))
+        .find(|file| file.is_file())
+        .or_else(|| views_paths.first().map(|views_path| views_path.join(path)))
 }
 
 impl Default for Workspace {
@@ -23,6 +47,132 @@ impl Default for Workspace {
     }
 }
 
+#[derive(Default)]
+pub struct Workspaces {
+    pub folders: Vec<Workspace>,
+    pub extra_view_dirs: Vec<String>,
+    pub default_layout: Option<String>,
+}
+
+impl Workspaces {
+    pub fn load_folder(&mut self, root: &Path) -> Result<(), String> {
+        let mut workspace = Workspace {
+            root: root.to_path_buf(),
+            ..Default::default()
+        };
+
+        // a folder that fails to load is kept so that a later Cargo.toml change can load it
+        let result = workspace.load(root);
+
+        self.remove_folder(root);
+        self.folders.push(workspace);
+
+        result
+    }
+
+    pub fn remove_folder(&mut self, root: &Path) {
+        self.folders.retain(|workspace| workspace.root != root);
+    }
+
+    pub fn reload(&mut self) {
+        for workspace in &mut self.folders {
+            let root = workspace.root.clone();
+            workspace.load(&root).unwrap_or_else(|e| {
+                debug!("Workspace couldn't load: {}", e);
+            });
+        }
+    }
+
+    pub fn members(&self) -> impl Iterator<Item = &Member> {
+        self.folders.iter().flat_map(|workspace| &workspace.members)
+    }
+
+    pub fn get_workspace_by_view(&self, view_path: &Path) -> Option<&Workspace> {
+        self.folders
+            .iter()
+            .filter(|workspace| view_path.starts_with(&workspace.root))
+            .max_by_key(|workspace| workspace.root.components().count())
+    }
+
+    pub fn get_member_by_view(&self, view_path: &Path) -> Option<&Member> {
+        self.get_workspace_by_view(view_path)?.get_member_by_view(view_path)
+    }
+
+    pub fn get_layout_path_by_view(&self, view_path: &Path) -> Option<PathBuf> {
+        let member = self.get_member_by_view(view_path)?;
+        let layout = member
+            .views_layout
+            .as_deref()
+            .or(self.default_layout.as_deref())
+            .unwrap_or(DEFAULT_LAYOUT);
+
+        member.views_file(layout)
+    }
+
+    pub fn view_roots(&self, view_path: &Path) -> Vec<PathBuf> {
+        let Some(workspace) = self.get_workspace_by_view(view_path) else {
+            return Vec::new();
+        };
+        let Some(member) = workspace.get_member_by_view(view_path) else {
+            return Vec::new();
+        };
+
+        let mut roots = member.view_roots();
+        roots.extend(self.extra_view_dirs.iter().map(|dir| workspace.root.join(dir)));
+        roots
+    }
+
+    pub fn resolve_view_path(&self, view_path: &Path, path: &str) -> Option<PathBuf> {
+        if let Some(resolved) = self.view_roots(view_path).iter().map(|root| root.join(path)).find(|x| x.is_file()) {
+            return Some(resolved);
+        }
+
+        let from_view_dir = view_path.parent()?.join(path);
+        from_view_dir.is_file().then_some(from_view_dir)
+    }
+
+    pub fn view_files(&self) -> Vec<PathBuf> {
+        self.collect_folder_view_files(&self.folders.iter().collect::<Vec<_>>())
+    }
+
+    pub fn folder_view_files(&self, root: &Path) -> Vec<PathBuf> {
+        self.collect_folder_view_files(&self.folders.iter().filter(|workspace| workspace.root == root).collect::<Vec<_>>())
+    }
+
+    pub fn all_view_roots(&self) -> Vec<PathBuf> {
+        let mut roots = self
+            .folders
+            .iter()
+            .flat_map(|workspace| self.folder_view_roots(workspace))
+            .collect::<Vec<_>>();
+        roots.sort();
+        roots.dedup();
+        roots
+    }
+
+    fn folder_view_roots(&self, workspace: &Workspace) -> Vec<PathBuf> {
+        workspace
+            .members
+            .iter()
+            .flat_map(|member| member.view_roots())
+            .chain(self.extra_view_dirs.iter().map(|dir| workspace.root.join(dir)))
+            .collect()
+    }
+
+    fn collect_folder_view_files(&self, folders: &[&Workspace]) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        for workspace in folders {
+            for root in self.folder_view_roots(workspace) {
+                Workspace::collect_view_files(&root, &mut files);
+            }
+        }
+
+        files.sort();
+        files.dedup();
+        files
+    }
+}
+
 impl Workspace {
     pub fn load(&mut self, root: &Path) -> Result<(), String> {
         let mut new_workspace = Self {
@@ -30,44 +180,36 @@ impl Workspace {
             ..Default::default()
         };
 
-        let cargo_toml = fs::read_to_string(root.join("Cargo.toml")).map_err(|e| e.to_string())?;
-        let cargo_toml: Value = toml::from_str(&cargo_toml).map_err(|e| e.to_string())?;
-
-        let member_paths = cargo_toml
-            .get("workspace")
-            .and_then(|workspace| workspace.get("members").and_then(|members| members.as_array()))
-            .map(|members| {
-                members
-                    .iter()
-                    .map(|member| root.join(member.to_string().trim_matches('"')))
-                    .collect::<Vec<_>>()
-            });
+        let (source, cargo_toml) = Self::read_manifest(root)?;
+        let workspace = cargo_toml.get("workspace");
 
-        if let Some(member_paths) = member_paths {
-            for member_path in member_paths {
-                debug!("MEMBER PATH: {}", member_path.to_str().unwrap());
-                let cargo_toml = fs::read_to_string(member_path.join("Cargo.toml")).map_err(|e| e.to_string())?;
-                let cargo_toml: Value = toml::from_str(&cargo_toml).map_err(|e| e.to_string())?;
-                let views = self.load_manifest(&cargo_toml)?;
-                let member = Member {
-                    path: member_path.clone(),
-                    views_path: member_path.join(views.0),
-                    views_layout: views.1.to_string(),
-                };
-
-                new_workspace.members.push(member);
-            }
-        } else {
-            let views = self.load_manifest(&cargo_toml)?;
-            let member = Member {
-                path: root.to_path_buf(),
-                views_path: root.join(views.0),
-                views_layout: views.1.to_string(),
-            };
+        if cargo_toml.get("package").is_some() || workspace.is_none() {
+            new_workspace.members.push(self.new_member(root, &source));
+        }
+
+        if let Some(workspace) = workspace {
+            let excluded = string_array(workspace, "exclude")
+                .iter()
+                .flat_map(|pattern| expand_glob(root, pattern))
+                .collect::<Vec<_>>();
+
+            let member_paths = string_array(workspace, "members")
+                .iter()
+                .flat_map(|pattern| expand_glob(root, pattern))
+                .collect::<Vec<_>>();
 
-            debug!("MEMBER PATH: {}", member.path.to_string_lossy().to_string());
+            for member_path in member_paths {
+                if excluded.iter().any(|excluded| member_path.starts_with(excluded))
+                    || new_workspace.members.iter().any(|member| member.path == member_path)
+                {
+                    continue;
+                }
 
-            new_workspace.members.push(member);
+                match Self::read_manifest(&member_path) {
+                    Ok((source, _)) => new_workspace.members.push(self.new_member(&member_path, &source)),
+                    Err(e) => error!("Skipping workspace member {}: {}", member_path.to_string_lossy(), e),
+                }
+            }
         }
 
         self.root = new_workspace.root;
@@ -76,32 +218,144 @@ impl Workspace {
         Ok(())
     }
 
-    fn load_manifest<'a>(&self, cargo_toml: &'a Value) -> Result<(&'a str, &'a str), String> {
-        let default_path = "views";
-        let default_layout = "layout.rs.html";
-        match cargo_toml.get("package.metadata.rshtml").and_then(|x| x.get("views")) {
-            Some(x) => {
-                let path = x.get("path").and_then(|x| x.as_str()).unwrap_or(default_path);
-                let layout = x.get("layout").and_then(|x| x.as_str()).unwrap_or(default_layout);
-                Ok((path, layout))
-            }
-            None => Ok((default_path, default_layout)),
+    fn read_manifest(path: &Path) -> Result<(String, Value), String> {
+        let source = fs::read_to_string(path.join("Cargo.toml")).map_err(|e| e.to_string())?;
+        let cargo_toml = toml::from_str(&source).map_err(|e| e.to_string())?;
+        Ok((source, cargo_toml))
+    }
+
+    fn new_member(&self, path: &Path, source: &str) -> Member {
+        let config = self.load_manifest(path, source);
+
+        Member {
+            path: path.to_path_buf(),
+            views_paths: config.views_paths.iter().map(|views_path| path.join(views_path)).collect(),
+            include_paths: config.include_paths.iter().map(|include_path| path.join(include_path)).collect(),
+            views_layout: config.views_layout,
+            manifest_diagnostics: config.diagnostics,
         }
     }
 
+    fn load_manifest(&self, path: &Path, source: &str) -> RshtmlConfig {
+        RshtmlConfig::parse(path, source)
+    }
+
     pub fn get_member_by_view(&self, view_path: &Path) -> Option<&Member> {
-        self.members.iter().find(|&member| view_path.starts_with(&member.path))
+        self.members
+            .iter()
+            .filter(|member| view_path.starts_with(&member.path))
+            .max_by_key(|member| member.path.components().count())
     }
 
-    pub fn get_layout_path_by_view(&self, view_path: &Path) -> Option<PathBuf> {
-        for member in &self.members {
-            if view_path.starts_with(&member.path) {
-                let mut path = member.views_path.clone();
-                path.push(&member.views_layout);
-                return Some(path);
+    pub fn collect_view_files(dir: &Path, files: &mut Vec<PathBuf>) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                Self::collect_view_files(&path, files);
+            } else if path.to_string_lossy().ends_with(".rs.html") {
+                files.push(path);
             }
         }
+    }
+}
+
+fn string_array<'a>(table: &'a Value, key: &str) -> Vec<&'a str> {
+    table
+        .get(key)
+        .and_then(|value| value.as_array())
+        .map(|values| values.iter().filter_map(|value| value.as_str()).collect())
+        .unwrap_or_default()
+}
+
+// members and excludes follow cargo's glob syntax, a pattern without one is taken as is
+fn expand_glob(root: &Path, pattern: &str) -> Vec<PathBuf> {
+    if !pattern.contains(['*', '?', '[']) {
+        return vec![root.join(pattern)];
+    }
+
+    let pattern = format!("{}/{}", glob::Pattern::escape(&root.to_string_lossy()), pattern);
+    let options = MatchOptions {
+        require_literal_separator: true,
+        require_literal_leading_dot: true,
+        ..Default::default()
+    };
+
+    match glob::glob_with(&pattern, options) {
+        Ok(paths) => paths.flatten().filter(|path| path.is_dir()).collect(),
+        Err(e) => {
+            error!("Invalid workspace glob `{}`: {}", pattern, e);
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::temp_files;
+
+    fn workspace_members(manifest: &str, members: &[&str]) -> Vec<String> {
+        let manifests = members.iter().map(|member| format!("{member}/Cargo.toml")).collect::<Vec<_>>();
+        let mut files = manifests
+            .iter()
+            .map(|manifest| (manifest.as_str(), "[package]\nname = \"member\"\n"))
+            .collect::<Vec<_>>();
+        files.extend([("Cargo.toml", manifest), ("crates/not-a-crate.txt", "")]);
+        let dir = temp_files(&files);
+
+        let mut workspace = Workspace::default();
+        workspace.load(dir.path()).unwrap();
+        let mut paths = workspace
+            .members
+            .iter()
+            .map(|member| member.path.strip_prefix(dir.path()).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn expands_member_globs() {
+        let members = workspace_members(
+            "[workspace]\nmembers = [\"crates/*\", \"app\"]\n",
+            &["crates/a", "crates/b", "crates/.hidden", "app"],
+        );
+
+        assert_eq!(members, ["app", "crates/a", "crates/b"]);
+    }
+
+    #[test]
+    fn expands_nested_member_globs() {
+        let members = workspace_members(
+            "[workspace]\nmembers = [\"crates/*/impl\", \"tools/**\", \"libs/[ab]?\"]\n",
+            &[
+                "crates/a/impl",
+                "crates/b/impl",
+                "tools/x",
+                "tools/x/y",
+                "libs/a1",
+                "libs/b2",
+                "libs/c3",
+            ],
+        );
+
+        assert_eq!(
+            members,
+            ["crates/a/impl", "crates/b/impl", "libs/a1", "libs/b2", "tools/x", "tools/x/y"]
+        );
+    }
+
+    #[test]
+    fn skips_excluded_members() {
+        let members = workspace_members(
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/old-*\", \"crates/b\"]\n",
+            &["crates/a", "crates/b", "crates/old-a", "crates/old-b"],
+        );
 
-        None
+        assert_eq!(members, ["crates/a"]);
     }
 }
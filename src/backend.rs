@@ -1,21 +1,215 @@
+mod completion;
+mod document_symbols;
+mod file_operations;
+mod hover;
 mod language_server;
+mod pull_diagnostics;
+mod references;
+mod rename;
 pub mod semantic_tokens_builder;
 mod server_capabilities;
-mod tree_extensions;
+pub mod tree_extensions;
 
 use crate::app_state::AppState;
+use crate::app_state::settings::Settings;
+use crate::logging;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
 use tower_lsp::Client;
-use tower_lsp::lsp_types::{Position, TextDocumentContentChangeEvent};
+use tower_lsp::lsp_types::notification::LogTrace;
+use tower_lsp::lsp_types::{
+    DidChangeWatchedFilesRegistrationOptions, FileSystemWatcher, GlobPattern, LogTraceParams, Position, Registration, SetTraceParams,
+    TextDocumentContentChangeEvent, TraceValue, Url,
+};
+use tracing::{debug, error, trace};
 use tree_sitter::{Point, Tree};
 
 pub struct Backend {
     pub client: Client,
-    pub state: AppState,
+    pub state: Arc<AppState>,
+    pub trace: RwLock<TraceValue>,
 }
 
 impl Backend {
     pub fn new(client: Client, app_state: AppState) -> Self {
-        Self { client, state: app_state }
+        Self {
+            client,
+            state: Arc::new(app_state),
+            trace: RwLock::new(TraceValue::Off),
+        }
+    }
+
+    pub async fn set_trace(&self, params: SetTraceParams) {
+        debug!("Trace set to {:?}", params.value);
+
+        if let Ok(mut trace) = self.trace.write() {
+            *trace = params.value;
+        }
+    }
+
+    async fn log_trace(&self, message: String, verbose: Option<String>) {
+        trace!("{}", message);
+
+        let trace = self.trace.read().map_or(TraceValue::Off, |trace| *trace);
+        if trace == TraceValue::Off {
+            return;
+        }
+
+        let verbose = verbose.filter(|_| trace == TraceValue::Verbose);
+        self.client.send_notification::<LogTrace>(LogTraceParams { message, verbose }).await;
+    }
+
+    fn apply_settings(&self, settings: Settings) {
+        if let Some(log_level) = &settings.log_level
+            && let Err(e) = logging::set_level(log_level)
+        {
+            error!("Invalid log level `{}`: {}", log_level, e);
+        }
+
+        if let Some(log_file) = &settings.log_file
+            && let Err(e) = logging::set_file(Path::new(log_file))
+        {
+            error!("Couldn't open log file `{}`: {}", log_file, e);
+        }
+
+        self.state.highlight.set_token_type_overrides(&settings.semantic_tokens);
+
+        if let Ok(mut workspace) = self.state.workspace.write() {
+            workspace.extra_view_dirs = settings.extra_view_dirs.clone();
+            workspace.default_layout = settings.default_layout.clone();
+        }
+
+        if let Ok(mut current) = self.state.settings.write() {
+            *current = settings;
+        }
+
+        self.state.index_changed();
+    }
+
+    async fn publish_view_diagnostics(&self, only: Option<&[String]>) {
+        if self.state.pull_diagnostics() {
+            self.refresh_pull_diagnostics().await;
+            return;
+        }
+
+        let diagnostics = if let Ok(views) = self.state.views.read() {
+            views
+                .iter()
+                .filter(|(uri, _)| only.is_none_or(|only| only.contains(uri)))
+                .filter_map(|(uri, view)| {
+                    let uri = Url::parse(uri).ok()?;
+                    let diagnostics = self.state.diagnostics(&uri, &self.state.view_summary(&uri, view));
+                    Some((uri, diagnostics, view.version as i32))
+                })
+                .collect::<Vec<_>>()
+        } else {
+            return;
+        };
+
+        for (uri, diagnostics, version) in diagnostics {
+            self.client.publish_diagnostics(uri, diagnostics, Some(version)).await;
+        }
+    }
+
+    async fn refresh_pull_diagnostics(&self) {
+        let refresh_support = self.state.client_capabilities.read().is_ok_and(|capabilities| {
+            capabilities
+                .workspace
+                .as_ref()
+                .and_then(|workspace| workspace.diagnostic.as_ref())
+                .and_then(|diagnostic| diagnostic.refresh_support)
+                .unwrap_or(false)
+        });
+
+        if refresh_support && let Err(e) = self.client.workspace_diagnostic_refresh().await {
+            debug!("Couldn't refresh diagnostics: {}", e);
+        }
+    }
+
+    async fn register_file_watchers(&self) {
+        let dynamic_registration = self.state.client_capabilities.read().is_ok_and(|capabilities| {
+            capabilities
+                .workspace
+                .as_ref()
+                .and_then(|workspace| workspace.did_change_watched_files)
+                .and_then(|watched_files| watched_files.dynamic_registration)
+                .unwrap_or(false)
+        });
+
+        if !dynamic_registration {
+            debug!("Client doesn't support dynamic registration of file watchers");
+            return;
+        }
+
+        let watchers = ["**/*.rs.html", "**/*.rs", "**/Cargo.toml"]
+            .into_iter()
+            .map(|pattern| FileSystemWatcher {
+                glob_pattern: GlobPattern::String(pattern.to_string()),
+                kind: None,
+            })
+            .collect();
+
+        let Ok(register_options) = serde_json::to_value(DidChangeWatchedFilesRegistrationOptions { watchers }) else {
+            return;
+        };
+
+        let registration = Registration {
+            id: "rshtml-watched-files".to_string(),
+            method: "workspace/didChangeWatchedFiles".to_string(),
+            register_options: Some(register_options),
+        };
+
+        if let Err(e) = self.client.register_capability(vec![registration]).await {
+            error!("Couldn't register file watchers: {}", e);
+        }
+    }
+
+    async fn publish_manifest_diagnostics(&self) {
+        let manifests = if let Ok(workspace) = self.state.workspace.read() {
+            workspace
+                .members()
+                .filter_map(|member| {
+                    let uri = Url::from_file_path(member.path.join("Cargo.toml")).ok()?;
+                    Some((uri, self.state.filter_diagnostics(member.manifest_diagnostics.clone())))
+                })
+                .collect::<Vec<_>>()
+        } else {
+            return;
+        };
+
+        for (uri, diagnostics) in manifests {
+            self.client.publish_diagnostics(uri, diagnostics, None).await;
+        }
+    }
+
+    // a copy of an open view, so long running work doesn't hold the views lock
+    fn view_snapshot(&self, uri: &Url) -> Option<(String, Tree)> {
+        let views = self.state.views.read().ok()?;
+        let view = views.get(&uri.to_string())?;
+
+        Some((view.source.clone(), view.tree.clone()))
+    }
+
+    // open views that extend this one, and the open layout this one extends
+    fn related_views(&self, uri: &Url) -> Vec<String> {
+        let Ok(file_path) = uri.to_file_path() else {
+            return Vec::new();
+        };
+
+        let Ok(views) = self.state.views.read() else {
+            debug!("views are locked");
+            return Vec::new();
+        };
+        let layout_path = views.get(&uri.to_string()).and_then(|view| view.layout_path.clone());
+
+        self.state
+            .find_child_views(&file_path)
+            .into_iter()
+            .map(|(path, _)| path)
+            .chain(layout_path)
+            .filter_map(|path| Url::from_file_path(path).ok().map(|uri| uri.to_string()))
+            .filter(|related| related != &uri.to_string() && views.contains_key(related))
+            .collect()
     }
 
     fn position_to_byte_offset(text: &str, position: Position) -> usize {
@@ -0,0 +1,293 @@
+use crate::app_state::view::View;
+use crate::backend::Backend;
+use crate::backend::tree_extensions::TreeExtensions;
+use std::fs;
+use tower_lsp::lsp_types::{
+    Command, CompletionItem, CompletionItemKind, CompletionTextEdit, InsertTextFormat, Position, Range, TextEdit, Url,
+};
+use tree_sitter::Tree;
+
+#[derive(Debug, PartialEq)]
+pub enum SyntaxContext {
+    Html,
+    Rust,
+    Comment,
+    Raw,
+    ComponentTag,
+    DirectivePath,
+    String,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CompletionContext {
+    Directive,
+    Component,
+    Parameter,
+    Path,
+    Text,
+    None,
+}
+
+impl Backend {
+    pub(crate) fn completion_context(view: &View, byte_offset: usize) -> CompletionContext {
+        let word = view.source[..byte_offset].trim_end_matches(|c: char| c.is_alphanumeric() || c == '_');
+        let trigger_offset = word.len().saturating_sub(1);
+
+        match word.chars().next_back() {
+            Some('@') if Self::syntax_context(view, trigger_offset, true) == SyntaxContext::Html => CompletionContext::Directive,
+            Some('<') if Self::syntax_context(view, trigger_offset, true) == SyntaxContext::Html => CompletionContext::Component,
+            _ => match Self::syntax_context(view, byte_offset, false) {
+                SyntaxContext::DirectivePath => CompletionContext::Path,
+                SyntaxContext::ComponentTag => CompletionContext::Parameter,
+                SyntaxContext::Html => CompletionContext::Text,
+                _ => CompletionContext::None,
+            },
+        }
+    }
+
+    pub(crate) fn syntax_context(view: &View, byte_offset: usize, skip_started: bool) -> SyntaxContext {
+        let root = view.tree.root_node();
+        let Some(mut node) = root.descendant_for_byte_range(byte_offset, byte_offset) else {
+            return SyntaxContext::Html;
+        };
+
+        loop {
+            // a half typed `@...` or `<...` is judged by what surrounds it
+            let started_here = skip_started && node.start_byte() == byte_offset;
+
+            match node.kind() {
+                _ if started_here => {}
+                "comment_block" => return SyntaxContext::Comment,
+                "raw_content" => return SyntaxContext::Raw,
+                "string_line" => {
+                    return match node.parent().map(|parent| parent.kind()) {
+                        Some("include_directive" | "use_directive" | "extends_directive") if node.start_byte() < byte_offset => {
+                            SyntaxContext::DirectivePath
+                        }
+                        _ => SyntaxContext::String,
+                    };
+                }
+                "html_text" | "html_inner_text" | "match_text" => return SyntaxContext::Html,
+                "rust_expr_simple" | "rust_expr_paren" | "rust_block" => return SyntaxContext::Rust,
+                "if_stmt" | "else_clause" | "for_stmt" | "while_stmt" | "match_stmt" | "match_stmt_arm" => {
+                    let head = node.child_by_field_name("head").or_else(|| node.child_by_field_name("pattern"));
+                    if head.is_some_and(|head| head.start_byte() <= byte_offset && byte_offset <= head.end_byte()) {
+                        return SyntaxContext::Rust;
+                    }
+                }
+                "component_tag" => {
+                    let mut cursor = node.walk();
+                    let open_tag_end = node
+                        .children(&mut cursor)
+                        .find(|child| matches!(child.kind(), "tag_close" | "tag_self_close"))
+                        .map_or(node.end_byte(), |child| child.start_byte());
+
+                    if byte_offset <= open_tag_end {
+                        return SyntaxContext::ComponentTag;
+                    }
+                }
+                _ => {}
+            }
+
+            match node.parent() {
+                Some(parent) => node = parent,
+                None => return SyntaxContext::Html,
+            }
+        }
+    }
+
+    pub(crate) fn directive_completion_items(&self, uri: &Url, view: &View) -> Vec<CompletionItem> {
+        let mut completion_items = view
+            .completion_items
+            .values()
+            .filter(|(item_char, _)| *item_char == '@')
+            .map(|(_, item)| item.clone())
+            .collect::<Vec<_>>();
+
+        completion_items.extend(self.state.completion_items.clone());
+
+        if let Ok(file_path) = uri.to_file_path() {
+            let mut section_names = self
+                .state
+                .find_child_views(&file_path)
+                .into_iter()
+                .flat_map(|(_, summary)| summary.section_names())
+                .collect::<Vec<_>>();
+            section_names.sort();
+            section_names.dedup();
+
+            completion_items.extend(section_names.iter().map(|name| View::section_completion_item(name).1));
+        }
+
+        completion_items
+    }
+
+    // completion asked for in plain text, without a typed `@`, so the snippets bring their own
+    pub(crate) fn text_completion_items(&self, uri: &Url, view: &View) -> Vec<CompletionItem> {
+        self.directive_completion_items(uri, view)
+            .into_iter()
+            .map(|mut item| {
+                let insert_text = item.insert_text.take().unwrap_or_else(|| item.label.clone());
+                item.insert_text = Some(format!("@{insert_text}"));
+                item
+            })
+            .collect()
+    }
+
+    pub(crate) fn component_completion_items(view: &View) -> Vec<CompletionItem> {
+        view.completion_items
+            .values()
+            .filter(|(item_char, _)| *item_char == '<')
+            .map(|(_, item)| item.clone())
+            .collect()
+    }
+
+    pub(crate) fn parameter_completion_items(&self, uri: &Url, view: &View, byte_offset: usize) -> Vec<CompletionItem> {
+        let Some(tag) = view.tree.find_ancestor(byte_offset, &["component_tag"]) else {
+            return Vec::new();
+        };
+
+        let Some(component_path) = tag
+            .child_by_field_name("name")
+            .and_then(|name| Tree::string_value(name, &view.source))
+            .and_then(|name| self.state.find_view(uri, view.use_directive_path(&name)?))
+        else {
+            return Vec::new();
+        };
+
+        let present = Tree::component_parameter_names(tag, &view.source);
+
+        self.state
+            .component_parameters(&component_path)
+            .into_iter()
+            .filter(|(parameter, _, _)| !present.contains(parameter))
+            .map(|(parameter, type_, required)| CompletionItem {
+                label: parameter.clone(),
+                kind: Some(CompletionItemKind::FIELD),
+                detail: type_,
+                insert_text_format: Some(InsertTextFormat::SNIPPET),
+                insert_text: Some(format!("{parameter}=$0")),
+                sort_text: Some(format!("{}{parameter}", if required { 0 } else { 1 })),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    pub(crate) fn path_completion_items(&self, uri: &Url, view: &View, byte_offset: usize, position: Position) -> Vec<CompletionItem> {
+        let Ok(file_path) = uri.to_file_path() else {
+            return Vec::new();
+        };
+
+        let Some(string_node) = view.tree.find_ancestor(byte_offset, &["string_line"]) else {
+            return Vec::new();
+        };
+
+        let typed = view.source[string_node.start_byte()..byte_offset].trim_start_matches(Tree::STRING_TRIMS);
+        let (directory, segment) = typed
+            .rsplit_once('/')
+            .map_or(("", typed), |(directory, segment)| (directory, segment));
+
+        let range = Range {
+            start: Position {
+                line: position.line,
+                character: position.character.saturating_sub(segment.encode_utf16().count() as u32),
+            },
+            end: position,
+        };
+
+        let roots = if typed.starts_with('.') {
+            file_path.parent().map(|parent| vec![parent.to_path_buf()]).unwrap_or_default()
+        } else {
+            self.state.view_roots(&file_path)
+        };
+
+        let mut completion_items: Vec<CompletionItem> = Vec::new();
+        for root in roots {
+            let Ok(entries) = fs::read_dir(root.join(directory)) else {
+                continue;
+            };
+
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let name = entry.file_name().to_string_lossy().to_string();
+
+                let item = if path.is_dir() {
+                    CompletionItem {
+                        label: format!("{name}/"),
+                        kind: Some(CompletionItemKind::FOLDER),
+                        text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                            range,
+                            new_text: format!("{name}/"),
+                        })),
+                        command: Some(Self::trigger_suggest_command()),
+                        sort_text: Some(format!("0{name}")),
+                        ..Default::default()
+                    }
+                } else if name.ends_with(".rs.html") && path != file_path {
+                    CompletionItem {
+                        label: name.clone(),
+                        kind: Some(CompletionItemKind::FILE),
+                        text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                            range,
+                            new_text: name.clone(),
+                        })),
+                        sort_text: Some(format!("1{name}")),
+                        ..Default::default()
+                    }
+                } else {
+                    continue;
+                };
+
+                if !completion_items.iter().any(|x| x.label == item.label) {
+                    completion_items.push(item);
+                }
+            }
+        }
+
+        completion_items
+    }
+
+    pub(crate) fn plain_completion_item(mut item: CompletionItem) -> CompletionItem {
+        if item.insert_text_format == Some(InsertTextFormat::SNIPPET) {
+            item.insert_text = item.insert_text.map(|snippet| plain_snippet(&snippet));
+            item.insert_text_format = Some(InsertTextFormat::PLAIN_TEXT);
+        }
+
+        item
+    }
+
+    pub(crate) fn trigger_suggest_command() -> Command {
+        Command {
+            title: "Trigger suggest".to_string(),
+            command: "editor.action.triggerSuggest".to_string(),
+            arguments: None,
+        }
+    }
+}
+
+fn plain_snippet(snippet: &str) -> String {
+    let mut text = String::new();
+    let mut chars = snippet.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => text.extend(chars.next()),
+            '$' if chars.peek() == Some(&'{') => {
+                chars.next();
+                while chars.next_if(char::is_ascii_digit).is_some() {}
+                chars.next_if_eq(&':');
+
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                    text.push(c);
+                }
+            }
+            '$' if chars.peek().is_some_and(char::is_ascii_digit) => while chars.next_if(char::is_ascii_digit).is_some() {},
+            _ => text.push(c),
+        }
+    }
+
+    text
+}
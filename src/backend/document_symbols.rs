@@ -0,0 +1,217 @@
+use crate::app_state::view::View;
+use crate::backend::Backend;
+use crate::backend::tree_extensions::TreeExtensions;
+use tower_lsp::lsp_types::{DocumentSymbol, Range, SymbolKind};
+use tree_sitter::{Language, Node, Parser, Tree};
+
+impl Backend {
+    pub(crate) fn document_symbols(view: &View) -> Vec<DocumentSymbol> {
+        let symbols = Self::child_symbols(view.tree.root_node(), &view.source);
+        let elements = Self::html_symbols(&view.tree, &view.source);
+
+        Self::nest_symbols(elements, symbols)
+    }
+
+    fn child_symbols(node: Node, source: &str) -> Vec<DocumentSymbol> {
+        let mut cursor = node.walk();
+        node.named_children(&mut cursor)
+            .flat_map(|child| Self::node_symbols(child, source))
+            .collect()
+    }
+
+    fn node_symbols(node: Node, source: &str) -> Vec<DocumentSymbol> {
+        let children = Self::child_symbols(node, source);
+
+        let Some((name, detail, kind, selection_node)) = Self::symbol_info(node, source) else {
+            return children;
+        };
+
+        #[allow(deprecated)]
+        let symbol = DocumentSymbol {
+            name,
+            detail,
+            kind,
+            tags: None,
+            deprecated: None,
+            range: Tree::from_range(node.range(), source),
+            selection_range: Tree::from_range(selection_node.range(), source),
+            children: if children.is_empty() { None } else { Some(children) },
+        };
+
+        vec![symbol]
+    }
+
+    fn symbol_info<'a>(node: Node<'a>, source: &str) -> Option<(String, Option<String>, SymbolKind, Node<'a>)> {
+        let field = |name: &str| node.child_by_field_name(name);
+        let text = |node: Node| Tree::string_value(node, source);
+
+        match node.kind() {
+            "section_block" | "section_directive" => {
+                let name_node = field("name")?;
+                Some((format!("@section {}", text(name_node)?), None, SymbolKind::NAMESPACE, name_node))
+            }
+            "render_directive" => {
+                let name_node = Tree::render_name(node)?;
+                Some((format!("@render {}", text(name_node)?), None, SymbolKind::NAMESPACE, name_node))
+            }
+            "include_directive" | "extends_directive" => {
+                let keyword = node.kind().trim_end_matches("_directive");
+                let path_node = field("path");
+                let detail = path_node.and_then(text);
+                Some((format!("@{keyword}"), detail, SymbolKind::FILE, path_node.unwrap_or(node)))
+            }
+            "use_directive" => {
+                let mut cursor = node.walk();
+                let alias_node = node
+                    .named_children(&mut cursor)
+                    .find(|child| child.kind() == "as_clause")
+                    .and_then(|as_clause| as_clause.child_by_field_name("alias"));
+                let path_node = field("path");
+                let name = match alias_node.and_then(text) {
+                    Some(alias) => format!("@use {alias}"),
+                    None => "@use".to_string(),
+                };
+                Some((
+                    name,
+                    path_node.and_then(text),
+                    SymbolKind::MODULE,
+                    alias_node.or(path_node).unwrap_or(node),
+                ))
+            }
+            "component_tag" => {
+                let name_node = field("name")?;
+                let parameters = Tree::component_parameter_names(node, source);
+                let detail = if parameters.is_empty() { None } else { Some(parameters.join(", ")) };
+                Some((format!("<{}>", text(name_node)?), detail, SymbolKind::STRUCT, name_node))
+            }
+            "if_stmt" | "for_stmt" | "while_stmt" | "match_stmt" => {
+                let keyword = node.kind().trim_end_matches("_stmt");
+                let head = Tree::statement_head(node, source, keyword)
+                    .map(|head| head.split_whitespace().collect::<Vec<_>>().join(" "))
+                    .unwrap_or_default();
+                Some((format!("@{keyword} {head}").trim_end().to_string(), None, SymbolKind::OBJECT, node))
+            }
+            "rust_block" => Some(("@{ }".to_string(), None, SymbolKind::OBJECT, node)),
+            _ => None,
+        }
+    }
+
+    fn html_symbols(tree: &Tree, source: &str) -> Vec<DocumentSymbol> {
+        let root = tree.root_node();
+        let mut cursor = root.walk();
+        let ranges = root
+            .named_children(&mut cursor)
+            .filter(|child| child.kind() == "html_text")
+            .map(|child| child.range())
+            .collect::<Vec<_>>();
+
+        if ranges.is_empty() {
+            return Vec::new();
+        }
+
+        let mut parser = Parser::new();
+        if parser.set_language(&Language::new(tree_sitter_html::LANGUAGE)).is_err() || parser.set_included_ranges(&ranges).is_err() {
+            return Vec::new();
+        }
+
+        let Some(html_tree) = parser.parse(source, None) else {
+            return Vec::new();
+        };
+
+        let html_root = html_tree.root_node();
+        let mut cursor = html_root.walk();
+        html_root
+            .named_children(&mut cursor)
+            .filter(|child| matches!(child.kind(), "element" | "script_element" | "style_element"))
+            .filter_map(|element| {
+                let start_tag = element.named_child(0)?;
+                let name = Self::html_element_name(start_tag, source)?;
+
+                #[allow(deprecated)]
+                let symbol = DocumentSymbol {
+                    name,
+                    detail: None,
+                    kind: SymbolKind::FIELD,
+                    tags: None,
+                    deprecated: None,
+                    range: Tree::from_range(element.range(), source),
+                    selection_range: Tree::from_range(start_tag.range(), source),
+                    children: None,
+                };
+
+                Some(symbol)
+            })
+            .collect()
+    }
+
+    fn html_element_name(start_tag: Node, source: &str) -> Option<String> {
+        let mut cursor = start_tag.walk();
+        let mut name = String::new();
+        let mut classes = String::new();
+
+        for child in start_tag.named_children(&mut cursor) {
+            match child.kind() {
+                "tag_name" => name.push_str(child.utf8_text(source.as_bytes()).ok()?),
+                "attribute" => {
+                    let Some(attribute_name) = child.named_child(0).and_then(|x| x.utf8_text(source.as_bytes()).ok()) else {
+                        continue;
+                    };
+                    let value = child.named_child(1).and_then(|x| Tree::string_value(x, source)).unwrap_or_default();
+
+                    match attribute_name {
+                        "id" if !value.is_empty() => name.push_str(&format!("#{value}")),
+                        "class" => value.split_whitespace().for_each(|class| classes.push_str(&format!(".{class}"))),
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if name.is_empty() { None } else { Some(name + &classes) }
+    }
+
+    fn nest_symbols(mut elements: Vec<DocumentSymbol>, symbols: Vec<DocumentSymbol>) -> Vec<DocumentSymbol> {
+        let contains = |outer: &Range, inner: &Range| outer.start <= inner.start && inner.end <= outer.end;
+        let mut roots = Vec::new();
+
+        for symbol in symbols {
+            match elements.iter_mut().find(|element| contains(&element.range, &symbol.range)) {
+                Some(element) => element.children.get_or_insert_with(Vec::new).push(symbol),
+                None => roots.push(symbol),
+            }
+        }
+
+        roots.extend(elements);
+        roots.sort_by_key(|symbol| symbol.range.start);
+
+        roots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::AppState;
+
+    #[test]
+    fn names_block_directives_by_their_head() {
+        let source = "@if user.is_admin {\n<p>admin</p>\n}\n@for item in items {\n<p>@item</p>\n}\n".to_string();
+        let state = AppState::setup();
+        let tree = state.parser.lock().unwrap().parse(&source, None).unwrap();
+        let view = View::new(source, tree, 1);
+
+        let symbols = Backend::document_symbols(&view)
+            .into_iter()
+            .map(|symbol| (symbol.name, symbol.kind))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            symbols,
+            [
+                ("@if user.is_admin".to_string(), SymbolKind::OBJECT),
+                ("@for item in items".to_string(), SymbolKind::OBJECT),
+            ]
+        );
+    }
+}
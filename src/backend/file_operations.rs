@@ -0,0 +1,78 @@
+use crate::backend::Backend;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tower_lsp::lsp_types::{TextEdit, Url};
+use tracing::debug;
+
+impl Backend {
+    pub(crate) fn moved_file_edits(&self, renames: &[(PathBuf, PathBuf)]) -> HashMap<Url, Vec<TextEdit>> {
+        let moved = |path: &Path| {
+            renames.iter().find_map(|(old, new)| {
+                let rest = path.strip_prefix(old).ok()?;
+                Some(if rest.as_os_str().is_empty() { new.clone() } else { new.join(rest) })
+            })
+        };
+
+        let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+
+        let Ok(index) = self.state.index.read() else {
+            debug!("index is locked");
+            return changes;
+        };
+
+        for (path, summary) in index.iter() {
+            let new_path = moved(path);
+
+            for (kind, directive_path, range) in &summary.path_directives {
+                let Some(directive_path) = directive_path else {
+                    continue;
+                };
+                let Some(target) = self.state.resolve_directive_path(path, kind, Some(directive_path)) else {
+                    continue;
+                };
+
+                let new_target = moved(&target);
+                if new_path.is_none() && new_target.is_none() {
+                    continue;
+                }
+
+                let root = self
+                    .state
+                    .view_roots(path)
+                    .into_iter()
+                    .find(|root| root.join(directive_path) == target);
+
+                let new_text = match (root, new_target) {
+                    (Some(_), None) => continue,
+                    (Some(root), Some(new_target)) => match new_target.strip_prefix(&root) {
+                        Ok(relative) => relative.to_string_lossy().replace('\\', "/"),
+                        Err(_) => relative_path(new_path.as_deref().unwrap_or(path), &new_target),
+                    },
+                    (None, new_target) => relative_path(new_path.as_deref().unwrap_or(path), new_target.as_deref().unwrap_or(&target)),
+                };
+
+                if new_text != *directive_path
+                    && let Ok(uri) = Url::from_file_path(path)
+                {
+                    changes.entry(uri).or_default().push(TextEdit { range: *range, new_text });
+                }
+            }
+        }
+
+        changes
+    }
+}
+
+fn relative_path(view_path: &Path, target: &Path) -> String {
+    let from = view_path
+        .parent()
+        .map(|parent| parent.components().collect::<Vec<_>>())
+        .unwrap_or_default();
+    let to = target.components().collect::<Vec<_>>();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut parts = vec!["..".to_string(); from.len() - common];
+    parts.extend(to[common..].iter().map(|part| part.as_os_str().to_string_lossy().to_string()));
+
+    parts.join("/")
+}
@@ -0,0 +1,94 @@
+use crate::app_state::view::View;
+use crate::backend::Backend;
+use crate::backend::tree_extensions::TreeExtensions;
+use crate::consts::DIRECTIVE_DOCS;
+use tower_lsp::lsp_types::Url;
+use tree_sitter::{Node, Tree};
+
+pub const HOVER_KINDS: [&str; 10] = [
+    "include_",
+    "extends_",
+    "section_",
+    "render_",
+    "render_body_",
+    "child_content_",
+    "raw_",
+    "use_",
+    "component_tag_identifier",
+    "render_directive",
+];
+
+impl Backend {
+    pub(crate) fn directive_hover(node: Node, source: &str) -> Option<String> {
+        let keyword = node.utf8_text(source.as_bytes()).ok()?.trim().trim_start_matches('@');
+        let keyword = if keyword.is_empty() {
+            node.kind().trim_end_matches('_')
+        } else {
+            keyword
+        };
+
+        DIRECTIVE_DOCS
+            .iter()
+            .find(|(name, _)| *name == keyword)
+            .map(|(name, doc)| format!("**@{name}**\n\n{doc}"))
+    }
+
+    pub(crate) fn component_hover(&self, uri: &Url, view: &View, node: Node) -> Option<String> {
+        let name = Tree::string_value(node, &view.source)?;
+        let tag = node.parent()?;
+        let mut value = format!("**{name}** component");
+
+        match view.use_directive_path(&name) {
+            Some(path) => match self.state.find_view(uri, path) {
+                Some(file_path) => value.push_str(&format!("\n\n`{}`", file_path.display())),
+                None => value.push_str(&format!("\n\n`{path}` (not found)")),
+            },
+            None => value.push_str("\n\nNo `use` directive found for this component."),
+        }
+
+        let parameters = Tree::component_parameter_names(tag, &view.source)
+            .into_iter()
+            .map(|name| format!("`{name}`"))
+            .collect::<Vec<_>>();
+
+        if !parameters.is_empty() {
+            value.push_str(&format!("\n\nParameters: {}", parameters.join(", ")));
+        }
+
+        Some(value)
+    }
+
+    pub(crate) fn render_hover(&self, uri: &Url, view: &View, node: Node) -> Option<String> {
+        let name = Tree::string_value(Tree::render_name(node)?, &view.source)?;
+
+        let file_path = uri.to_file_path().ok()?;
+        let mut definitions = self
+            .state
+            .find_child_views(&file_path)
+            .into_iter()
+            .flat_map(|(view_path, summary)| {
+                let view_uri = Url::from_file_path(&view_path).ok();
+                let file_name = view_path.file_name().map(|x| x.to_string_lossy().to_string()).unwrap_or_default();
+                summary
+                    .sections
+                    .into_iter()
+                    .filter(|(section_name, _)| *section_name == name)
+                    .filter_map(move |(_, range)| {
+                        let line = range.start.line + 1;
+                        Some(format!("- [{file_name}:{line}]({}#L{line})", view_uri.as_ref()?))
+                    })
+            })
+            .collect::<Vec<_>>();
+        definitions.sort();
+
+        let mut value = format!("**{name}** section");
+        if definitions.is_empty() {
+            value.push_str("\n\nNo view defines this section.");
+        } else {
+            value.push_str("\n\nDefined in:\n");
+            value.push_str(&definitions.join("\n"));
+        }
+
+        Some(value)
+    }
+}
@@ -1,41 +1,74 @@
+use crate::app_state::diagnostics::UNRESOLVED_PATH;
+use crate::app_state::formatter;
+use crate::app_state::index::ViewSummary;
+use crate::app_state::settings::{Settings, SnippetStyle};
 use crate::app_state::view::View;
 use crate::backend::Backend;
+use crate::backend::completion::CompletionContext;
+use crate::backend::hover::HOVER_KINDS;
 use crate::backend::server_capabilities::{semantic_tokens_capabilities, workspace_capabilities};
 use crate::backend::tree_extensions::TreeExtensions;
+use std::collections::HashMap;
 use tower_lsp::jsonrpc::Error;
 use tower_lsp::lsp_types::{
-    CompletionItem, CompletionList, CompletionOptions, CompletionParams, CompletionResponse, DidChangeTextDocumentParams,
-    DidChangeWatchedFilesParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams, InitializeParams, InitializeResult,
-    InitializedParams, MessageType, SemanticTokens, SemanticTokensDelta, SemanticTokensDeltaParams, SemanticTokensFullDeltaResult,
-    SemanticTokensParams, SemanticTokensRangeParams, SemanticTokensRangeResult, SemanticTokensResult, ServerCapabilities, ServerInfo,
-    TextDocumentSyncCapability, TextDocumentSyncKind,
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, CodeActionProviderCapability, CodeActionResponse, CompletionList,
+    CompletionOptions, CompletionParams, CompletionResponse, CompletionTriggerKind, ConfigurationItem, DiagnosticOptions,
+    DiagnosticServerCapabilities, DidChangeConfigurationParams, DidChangeTextDocumentParams, DidChangeWatchedFilesParams,
+    DidChangeWorkspaceFoldersParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams, DocumentDiagnosticParams,
+    DocumentDiagnosticReportResult, DocumentFormattingParams, DocumentRangeFormattingParams, DocumentSymbolParams, DocumentSymbolResponse,
+    GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents, HoverParams, HoverProviderCapability, InitializeParams,
+    InitializeResult, InitializedParams, Location, MarkupContent, MarkupKind, MessageType, NumberOrString, OneOf, PrepareRenameResponse,
+    Range, ReferenceParams, RenameFilesParams, RenameOptions, RenameParams, SemanticTokens, SemanticTokensDelta, SemanticTokensDeltaParams,
+    SemanticTokensFullDeltaResult, SemanticTokensParams, SemanticTokensRangeParams, SemanticTokensRangeResult, SemanticTokensResult,
+    ServerCapabilities, ServerInfo, TextDocumentPositionParams, TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Url,
+    WorkDoneProgressOptions, WorkspaceDiagnosticParams, WorkspaceDiagnosticReportResult, WorkspaceEdit,
 };
 use tower_lsp::{LanguageServer, jsonrpc};
 use tracing::{debug, error};
-
+use tree_sitter::Tree;
 
 #[tower_lsp::async_trait]
 impl LanguageServer for Backend {
     async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult, Error> {
         debug!("The Initialize request has been received and is being processed...");
-        let workspace_root_path = params
+        if let Some(settings) = params.initialization_options.clone().and_then(Settings::from_value) {
+            self.apply_settings(settings);
+        }
+
+        if let Ok(mut client_capabilities) = self.state.client_capabilities.write() {
+            *client_capabilities = params.capabilities.clone();
+        }
+
+        if let Some(value) = params.trace
+            && let Ok(mut trace) = self.trace.write()
+        {
+            *trace = value;
+        }
+
+        let workspace_root_paths = params
             .workspace_folders
-            .as_ref()
-            .and_then(|folders| folders.first())
-            .and_then(|folder| folder.uri.to_file_path().ok());
+            .iter()
+            .flatten()
+            .filter_map(|folder| folder.uri.to_file_path().ok())
+            .collect::<Vec<_>>();
 
-        if let Some(path) = workspace_root_path {
+        for path in workspace_root_paths {
             debug!("Workspace root path: {:?}", path);
             self.client
                 .log_message(MessageType::INFO, format!("Workspace root path: {path:?}"))
                 .await;
 
             let mut workspace = self.state.workspace.write().unwrap();
-            workspace.load(&path).unwrap_or_else(|e| {
+            workspace.load_folder(&path).unwrap_or_else(|e| {
                 debug!("Workspace couldn't load: {}", e);
             });
         }
 
+        let view_roots = match self.state.workspace.read() {
+            Ok(workspace) => workspace.all_view_roots(),
+            Err(_) => Vec::new(),
+        };
+
         debug!("Sending an initialize response.");
         Ok(InitializeResult {
             capabilities: ServerCapabilities {
@@ -47,14 +80,30 @@ impl LanguageServer for Backend {
                 //     will_save_wait_until: Some(true),
                 //     save: Some(TextDocumentSyncSaveOptions::SaveOptions(SaveOptions { include_text: Some(false) })),
                 // })),
-                //document_formatting_provider: Some(OneOf::Left(true)),
+                document_formatting_provider: Some(OneOf::Left(true)),
+                document_range_formatting_provider: Some(OneOf::Left(true)),
                 semantic_tokens_provider: semantic_tokens_capabilities(),
                 completion_provider: Some(CompletionOptions {
                     resolve_provider: Some(false),
-                    trigger_characters: Some(vec!["@".to_string(), "<".to_string()]),
+                    trigger_characters: Some(vec!["@".to_string(), "<".to_string(), "\"".to_string(), "/".to_string()]),
                     ..Default::default()
                 }),
-                workspace: workspace_capabilities(),
+                definition_provider: Some(OneOf::Left(true)),
+                diagnostic_provider: Some(DiagnosticServerCapabilities::Options(DiagnosticOptions {
+                    identifier: Some("rshtml".to_string()),
+                    inter_file_dependencies: true,
+                    workspace_diagnostics: true,
+                    work_done_progress_options: WorkDoneProgressOptions::default(),
+                })),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                document_symbol_provider: Some(OneOf::Left(true)),
+                code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+                references_provider: Some(OneOf::Left(true)),
+                rename_provider: Some(OneOf::Right(RenameOptions {
+                    prepare_provider: Some(true),
+                    work_done_progress_options: WorkDoneProgressOptions::default(),
+                })),
+                workspace: workspace_capabilities(&view_roots),
                 //position_encoding:Some(PositionEncodingKind::UTF8),
                 ..Default::default()
             },
@@ -67,6 +116,13 @@ impl LanguageServer for Backend {
 
     async fn initialized(&self, _: InitializedParams) {
         self.client.log_message(MessageType::INFO, "rshtml LSP initialized!").await;
+        self.register_file_watchers().await;
+
+        // views opened while indexing ran were checked against a partial index
+        self.state.index_workspace().await;
+        self.state.refresh_layouts();
+        self.publish_view_diagnostics(None).await;
+        self.publish_manifest_diagnostics().await;
     }
 
     async fn shutdown(&self) -> Result<(), Error> {
@@ -75,7 +131,7 @@ impl LanguageServer for Backend {
 
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
         let msg = format!("Opened file: {}", &params.text_document.uri);
-        self.client.log_message(MessageType::INFO, msg).await;
+        self.log_trace(msg, Some(format!("version {}", params.text_document.version))).await;
 
         let uri_str = params.text_document.uri.to_string();
         let text = params.text_document.text;
@@ -91,49 +147,60 @@ impl LanguageServer for Backend {
             return;
         };
 
-        let include_paths = tree.find_includes(&self.state.language, &text);
-        debug!("Include paths: {:?}", include_paths);
-
-        let use_directives = tree.find_uses(&self.state.language, &text);
-        debug!("Use directives: {:?}", use_directives);
+        let summary = ViewSummary::new(&tree, &self.state.language, &text);
+        debug!("Include paths: {:?}", summary.include_paths);
+        debug!("Use directives: {:?}", summary.use_directives);
 
-        let extends = tree.find_extends(&self.state.language, &text);
-        let layout_path = extends.and_then(|extends| self.state.find_layout(&params.text_document.uri, extends.as_deref()));
+        let layout_path = summary
+            .extends
+            .as_ref()
+            .and_then(|extends| self.state.find_layout(&params.text_document.uri, extends.as_deref()));
         debug!("Layout path: {:?}", layout_path);
+        debug!("Sections: {:?}", summary.sections);
 
-        let section_names = tree.find_sections(&self.state.language, &text);
-        debug!("Sections: {:?}", section_names);
+        let errors = self.state.diagnostics(&params.text_document.uri, &summary);
 
-        let errors = {
+        {
             let mut view = View::new(text, tree, params.text_document.version as usize);
             view.layout_path = layout_path;
-            view.include_paths = include_paths;
-            view.use_directives = use_directives;
+            view.include_paths = summary.include_paths.clone();
+            view.use_directives = summary.use_directives.clone();
             view.create_use_directive_completion_items();
-            view.section_names = section_names;
+            view.section_names = summary.section_names();
             view.create_section_completion_items();
 
             let mut views = self.state.views.write().unwrap();
+            views.insert(uri_str, view);
+        }
 
-            let errors = view.tree.find_error(&self.state.language, &view.source);
+        self.state.update_index(&params.text_document.uri, summary);
 
-            views.insert(uri_str, view);
+        // sections are checked across a layout and its child views, so the open ones are checked again too
+        let related = self.related_views(&params.text_document.uri);
 
-            errors
-        };
+        if !self.state.pull_diagnostics() {
+            self.client
+                .publish_diagnostics(params.text_document.uri, errors, Some(params.text_document.version))
+                .await;
+        }
 
-        self.client
-            .publish_diagnostics(params.text_document.uri, errors, Some(params.text_document.version))
-            .await;
+        if !related.is_empty() {
+            self.publish_view_diagnostics(Some(&related)).await;
+        }
     }
 
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
         let msg = format!("Changed file: {}", &params.text_document.uri);
-        self.client.log_message(MessageType::INFO, msg).await;
+        let verbose = format!(
+            "version {}, {} content change(s)",
+            params.text_document.version,
+            params.content_changes.len()
+        );
+        self.log_trace(msg, Some(verbose)).await;
 
         let uri_str = params.text_document.uri.to_string();
 
-        let errors = if let Ok(mut views) = self.state.views.write()
+        let (errors, summary) = if let Ok(mut views) = self.state.views.write()
             && let Some(view) = views.get_mut(&uri_str)
         {
             if view.version >= params.text_document.version as usize {
@@ -145,22 +212,21 @@ impl LanguageServer for Backend {
             if let Ok(mut parser) = self.state.parser.lock()
                 && let Some(tree) = parser.parse(&view.source, Some(&view.tree))
             {
-                let extends = tree.find_extends(&self.state.language, &view.source);
-                let layout_path = extends.and_then(|extends| self.state.find_layout(&params.text_document.uri, extends.as_deref()));
-
-                let include_paths = tree.find_includes(&self.state.language, &view.source);
-                let use_directives = tree.find_uses(&self.state.language, &view.source);
-                let section_names = tree.find_sections(&self.state.language, &view.source);
+                let summary = ViewSummary::new(&tree, &self.state.language, &view.source);
+                let layout_path = summary
+                    .extends
+                    .as_ref()
+                    .and_then(|extends| self.state.find_layout(&params.text_document.uri, extends.as_deref()));
 
                 view.version = params.text_document.version as usize;
                 view.tree = tree;
                 view.layout_path = layout_path;
-                view.include_paths = include_paths;
-                view.use_directives = use_directives;
+                view.include_paths = summary.include_paths.clone();
+                view.use_directives = summary.use_directives.clone();
                 view.update_use_directive_completion_items();
-                view.section_names = section_names;
+                view.section_names = summary.section_names();
 
-                view.tree.find_error(&self.state.language, &view.source)
+                (self.state.diagnostics(&params.text_document.uri, &summary), summary)
             } else {
                 error!("Error while parsing tree");
                 return;
@@ -170,19 +236,34 @@ impl LanguageServer for Backend {
             return;
         };
 
-        self.client
-            .publish_diagnostics(params.text_document.uri, errors, Some(params.text_document.version))
-            .await;
+        self.state.update_index(&params.text_document.uri, summary);
+
+        // sections are checked across a layout and its child views, so the open ones are checked again too
+        let related = self.related_views(&params.text_document.uri);
+
+        if !self.state.pull_diagnostics() {
+            self.client
+                .publish_diagnostics(params.text_document.uri, errors, Some(params.text_document.version))
+                .await;
+        }
+
+        if !related.is_empty() {
+            self.publish_view_diagnostics(Some(&related)).await;
+        }
     }
 
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
         let msg = format!("Closed file: {}", &params.text_document.uri);
-        self.client.log_message(MessageType::INFO, msg).await;
+        self.log_trace(msg, None).await;
         let uri_str = params.text_document.uri.to_string();
 
         if let Ok(mut views) = self.state.views.write() {
             views.remove(&uri_str);
         }
+
+        if let Ok(path) = params.text_document.uri.to_file_path() {
+            self.state.index_file(&path);
+        }
     }
 
     async fn semantic_tokens_full(&self, params: SemanticTokensParams) -> Result<Option<SemanticTokensResult>, Error> {
@@ -277,51 +358,34 @@ impl LanguageServer for Backend {
 
     async fn completion(&self, params: CompletionParams) -> jsonrpc::Result<Option<CompletionResponse>> {
         let uri = params.text_document_position.text_document.uri;
-        let trigger_char = params.context.and_then(|ctx| ctx.trigger_character).and_then(|s| s.chars().next());
+        let position = params.text_document_position.position;
+
+        // directives in html text are offered when asked for, not on the `"` and `/` typed in attributes and closing tags
+        let text_requested = params.context.as_ref().is_none_or(|context| {
+            context.trigger_kind == CompletionTriggerKind::INVOKED || context.trigger_character.as_deref() == Some("@")
+        });
 
         if let Ok(views) = self.state.views.read()
             && let Some(view) = views.get(&uri.to_string())
         {
-            let mut completion_items: Vec<CompletionItem> = Vec::new();
-
-            if let Some(tc) = trigger_char {
-                for (item_char, item) in view.completion_items.values() {
-                    if *item_char == tc {
-                        completion_items.push(item.clone());
-                    }
-                }
-
-                if tc == '@' {
-                    completion_items.extend(self.state.completion_items.clone());
-                }
-            } else {
-                for (_, item) in view.completion_items.values() {
-                    completion_items.push(item.clone());
-                }
-
-                completion_items.extend(self.state.completion_items.clone());
-            }
+            let byte_offset = Self::position_to_byte_offset(&view.source, position);
+
+            let completion_items = match Self::completion_context(view, byte_offset) {
+                CompletionContext::Directive => self.directive_completion_items(&uri, view),
+                CompletionContext::Component => Self::component_completion_items(view),
+                CompletionContext::Parameter => self.parameter_completion_items(&uri, view, byte_offset),
+                CompletionContext::Path => self.path_completion_items(&uri, view, byte_offset, position),
+                CompletionContext::Text if text_requested => self.text_completion_items(&uri, view),
+                CompletionContext::Text => Vec::new(),
+                CompletionContext::None => Vec::new(),
+            };
 
-            if Some('@') == trigger_char || trigger_char.is_none() {
-                for v in views.values() {
-                    let is_layout = uri.to_file_path().map(|x| v.layout_path == Some(x)).unwrap_or(false);
-                    if is_layout {
-                        let items = v
-                            .completion_items
-                            .iter()
-                            .filter_map(|(name, value)| {
-                                if name.starts_with("section_") {
-                                    Some(value.1.clone())
-                                } else {
-                                    None
-                                }
-                            })
-                            .collect::<Vec<_>>();
-
-                        completion_items.extend(items);
-                    }
+            let completion_items = match self.state.settings.read() {
+                Ok(settings) if settings.snippet_style == SnippetStyle::Plain => {
+                    completion_items.into_iter().map(Self::plain_completion_item).collect()
                 }
-            }
+                _ => completion_items,
+            };
 
             return Ok(Some(CompletionResponse::List(CompletionList {
                 is_incomplete: true,
@@ -333,22 +397,490 @@ impl LanguageServer for Backend {
         Ok(None)
     }
 
+    async fn goto_definition(&self, params: GotoDefinitionParams) -> jsonrpc::Result<Option<GotoDefinitionResponse>> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+
+        let directive = if let Ok(views) = self.state.views.read()
+            && let Some(view) = views.get(&uri.to_string())
+        {
+            let byte_offset = Self::position_to_byte_offset(&view.source, position);
+            view.tree
+                .find_ancestor(
+                    byte_offset,
+                    &[
+                        "component_tag_identifier",
+                        "include_directive",
+                        "use_directive",
+                        "extends_directive",
+                    ],
+                )
+                .map(|node| {
+                    let path = if node.kind() == "component_tag_identifier" {
+                        Tree::string_value(node, &view.source).and_then(|name| view.use_directive_path(&name).map(|path| path.to_string()))
+                    } else {
+                        node.child_by_field_name("path")
+                            .and_then(|path| Tree::string_value(path, &view.source))
+                    };
+                    (node.kind(), path)
+                })
+        } else {
+            debug!("Error while getting definition");
+            return Ok(None);
+        };
+
+        let target_path = match directive {
+            Some(("extends_directive", path)) => self.state.find_layout(&uri, path.as_deref()),
+            Some((_, Some(path))) => self.state.find_view(&uri, &path),
+            _ => None,
+        };
+
+        let location = target_path
+            .filter(|path| path.is_file())
+            .and_then(|path| Url::from_file_path(path).ok())
+            .map(|uri| Location {
+                uri,
+                range: Range::default(),
+            });
+
+        Ok(location.map(GotoDefinitionResponse::Scalar))
+    }
+
+    async fn hover(&self, params: HoverParams) -> jsonrpc::Result<Option<Hover>> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+
+        if let Ok(views) = self.state.views.read()
+            && let Some(view) = views.get(&uri.to_string())
+        {
+            let byte_offset = Self::position_to_byte_offset(&view.source, position);
+            let Some(node) = view.tree.find_ancestor(byte_offset, &HOVER_KINDS) else {
+                return Ok(None);
+            };
+
+            let value = match node.kind() {
+                "component_tag_identifier" => self.component_hover(&uri, view, node),
+                "render_directive" => self.render_hover(&uri, view, node),
+                _ => Self::directive_hover(node, &view.source),
+            };
+
+            return Ok(value.map(|value| Hover {
+                contents: HoverContents::Markup(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value,
+                }),
+                range: Some(Tree::from_range(node.range(), &view.source)),
+            }));
+        }
+
+        debug!("Error while getting hover");
+        Ok(None)
+    }
+
+    async fn document_symbol(&self, params: DocumentSymbolParams) -> jsonrpc::Result<Option<DocumentSymbolResponse>> {
+        let uri_str = params.text_document.uri.to_string();
+
+        if let Ok(views) = self.state.views.read()
+            && let Some(view) = views.get(&uri_str)
+        {
+            let symbols = Self::document_symbols(view);
+            return Ok(Some(DocumentSymbolResponse::Nested(symbols)));
+        }
+
+        debug!("Error while getting document symbols");
+        Ok(None)
+    }
+
+    async fn formatting(&self, params: DocumentFormattingParams) -> jsonrpc::Result<Option<Vec<TextEdit>>> {
+        let Some((source, tree)) = self.view_snapshot(&params.text_document.uri) else {
+            debug!("Error while formatting");
+            return Ok(None);
+        };
+
+        let options = params.options;
+        let edits = tokio::task::spawn_blocking(move || {
+            let edits = formatter::format(&source, &tree, &options, None);
+            formatter::text_edits(&source, edits)
+        })
+        .await;
+
+        Ok(edits.ok())
+    }
+
+    async fn range_formatting(&self, params: DocumentRangeFormattingParams) -> jsonrpc::Result<Option<Vec<TextEdit>>> {
+        let Some((source, tree)) = self.view_snapshot(&params.text_document.uri) else {
+            debug!("Error while formatting range");
+            return Ok(None);
+        };
+
+        let start = Self::position_to_byte_offset(&source, params.range.start);
+        let end = Self::position_to_byte_offset(&source, params.range.end);
+
+        let options = params.options;
+        let edits = tokio::task::spawn_blocking(move || {
+            let edits = formatter::format(&source, &tree, &options, Some(start..end));
+            formatter::text_edits(&source, edits)
+        })
+        .await;
+
+        Ok(edits.ok())
+    }
+
+    async fn references(&self, params: ReferenceParams) -> jsonrpc::Result<Option<Vec<Location>>> {
+        let uri = params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
+
+        if let Ok(views) = self.state.views.read()
+            && let Some(view) = views.get(&uri.to_string())
+        {
+            let byte_offset = Self::position_to_byte_offset(&view.source, position);
+            let locations = self.find_references(&uri, view, byte_offset, params.context.include_declaration);
+            return Ok(Some(locations));
+        }
+
+        debug!("Error while getting references");
+        Ok(None)
+    }
+
+    async fn diagnostic(&self, params: DocumentDiagnosticParams) -> jsonrpc::Result<DocumentDiagnosticReportResult> {
+        let report = self.document_diagnostic_report(&params.text_document.uri, params.previous_result_id.as_deref());
+        Ok(DocumentDiagnosticReportResult::Report(report))
+    }
+
+    async fn workspace_diagnostic(&self, params: WorkspaceDiagnosticParams) -> jsonrpc::Result<WorkspaceDiagnosticReportResult> {
+        let report = self.workspace_diagnostic_report(params.previous_result_ids).await;
+        Ok(WorkspaceDiagnosticReportResult::Report(report))
+    }
+
+    async fn prepare_rename(&self, params: TextDocumentPositionParams) -> jsonrpc::Result<Option<PrepareRenameResponse>> {
+        let uri = params.text_document.uri;
+
+        if let Ok(views) = self.state.views.read()
+            && let Some(view) = views.get(&uri.to_string())
+        {
+            let byte_offset = Self::position_to_byte_offset(&view.source, params.position);
+            let response = self
+                .rename_target(&uri, view, byte_offset)
+                .map(|(_, range, placeholder)| PrepareRenameResponse::RangeWithPlaceholder { range, placeholder });
+
+            return Ok(response);
+        }
+
+        debug!("Error while preparing rename");
+        Ok(None)
+    }
+
+    async fn rename(&self, params: RenameParams) -> jsonrpc::Result<Option<WorkspaceEdit>> {
+        let uri = params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
+
+        if let Ok(views) = self.state.views.read()
+            && let Some(view) = views.get(&uri.to_string())
+        {
+            let byte_offset = Self::position_to_byte_offset(&view.source, position);
+            let Some((target, _, _)) = self.rename_target(&uri, view, byte_offset) else {
+                return Ok(None);
+            };
+
+            return self.rename_edit(&uri, view, target, &params.new_name);
+        }
+
+        debug!("Error while renaming");
+        Ok(None)
+    }
+
+    async fn code_action(&self, params: CodeActionParams) -> jsonrpc::Result<Option<CodeActionResponse>> {
+        let uri = params.text_document.uri;
+
+        let Ok(views) = self.state.views.read() else {
+            debug!("Error while getting code actions");
+            return Ok(None);
+        };
+        let Some(view) = views.get(&uri.to_string()) else {
+            return Ok(None);
+        };
+
+        let mut actions = Vec::new();
+        for diagnostic in params.context.diagnostics {
+            if diagnostic.code != Some(NumberOrString::String(UNRESOLVED_PATH.to_string())) {
+                continue;
+            }
+
+            let start_byte = Self::position_to_byte_offset(&view.source, diagnostic.range.start);
+            let end_byte = Self::position_to_byte_offset(&view.source, diagnostic.range.end);
+            let Some(path) = view.source.get(start_byte..end_byte) else {
+                continue;
+            };
+
+            let Some(suggestion) = self.state.suggest_view_path(&uri, path) else {
+                continue;
+            };
+
+            let edit = TextEdit {
+                range: diagnostic.range,
+                new_text: suggestion.clone(),
+            };
+
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: format!("Change to `{suggestion}`"),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![diagnostic]),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(uri.clone(), vec![edit])])),
+                    ..Default::default()
+                }),
+                is_preferred: Some(true),
+                ..Default::default()
+            }));
+        }
+
+        Ok(Some(actions))
+    }
+
+    async fn will_rename_files(&self, params: RenameFilesParams) -> jsonrpc::Result<Option<WorkspaceEdit>> {
+        let renames = params
+            .files
+            .iter()
+            .filter_map(|file| {
+                let old_path = Url::parse(&file.old_uri).ok()?.to_file_path().ok()?;
+                let new_path = Url::parse(&file.new_uri).ok()?.to_file_path().ok()?;
+                Some((old_path, new_path))
+            })
+            .collect::<Vec<_>>();
+
+        let changes = self.moved_file_edits(&renames);
+        if changes.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(WorkspaceEdit {
+            changes: Some(changes),
+            ..Default::default()
+        }))
+    }
+
     async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
         let cargo_toml_changed = params.changes.iter().any(|event| event.uri.path().ends_with("/Cargo.toml"));
 
-        if !cargo_toml_changed {
+        if cargo_toml_changed {
+            debug!("Cargo.toml changed. Re-analyzing...");
+
+            if let Ok(mut workspace) = self.state.workspace.write() {
+                workspace.reload();
+            }
+
+            self.state.index_workspace().await;
+            self.state.refresh_layouts();
+            self.publish_view_diagnostics(None).await;
+            self.publish_manifest_diagnostics().await;
+
+            debug!("Workspace re-analysis complete.");
+        }
+
+        let rust_changed = params.changes.iter().any(|event| event.uri.path().ends_with(".rs"));
+
+        if rust_changed && !cargo_toml_changed {
+            debug!("Rust sources changed. Re-reading component structs...");
+            self.state.index_components().await;
+            self.publish_view_diagnostics(None).await;
+        }
+
+        let open_uris = match self.state.views.read() {
+            Ok(views) => views.keys().cloned().collect::<Vec<_>>(),
+            Err(_) => Vec::new(),
+        };
+
+        let changed_paths = params
+            .changes
+            .iter()
+            .filter(|event| event.uri.path().ends_with(".rs.html"))
+            // open views are kept up to date by the editor, even when the file is deleted on disk
+            .filter(|event| !open_uris.contains(&event.uri.to_string()))
+            .filter_map(|event| event.uri.to_file_path().ok())
+            .collect::<Vec<_>>();
+
+        if changed_paths.is_empty() {
             return;
         }
 
-        debug!("Cargo.toml changed. Re-analyzing...");
+        debug!("View files changed on disk: {:?}", changed_paths);
 
-        if let Ok(mut workspace) = self.state.workspace.write() {
-            let root = workspace.root.clone();
-            workspace.load(&root).unwrap_or_else(|e| {
-                debug!("Workspace couldn't load: {}", e);
-            });
+        let mut layout_paths = self.state.layout_paths(&changed_paths);
+        for path in &changed_paths {
+            self.state.index_file(path);
+        }
+        layout_paths.extend(self.state.layout_paths(&changed_paths));
+
+        let mut uris = self.state.dependent_views(&changed_paths);
+        uris.extend(
+            layout_paths
+                .iter()
+                .filter_map(|path| Some(Url::from_file_path(path).ok()?.to_string())),
+        );
+
+        self.state.refresh_layouts();
+        self.publish_view_diagnostics(Some(&uris)).await;
+    }
+
+    async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
+        let value = if params.settings.is_null() {
+            let items = vec![ConfigurationItem {
+                scope_uri: None,
+                section: Some("rshtml".to_string()),
+            }];
+
+            match self.client.configuration(items).await {
+                Ok(mut values) if !values.is_empty() => values.remove(0),
+                _ => {
+                    debug!("Couldn't get configuration from the client");
+                    return;
+                }
+            }
+        } else {
+            params.settings
+        };
+
+        let Some(settings) = Settings::from_value(value) else {
+            return;
+        };
+
+        debug!("Configuration changed: {:?}", settings);
+        self.apply_settings(settings);
+
+        self.state.index_workspace().await;
+        self.state.refresh_layouts();
+        self.publish_view_diagnostics(None).await;
+        self.publish_manifest_diagnostics().await;
+
+        if let Err(e) = self.client.semantic_tokens_refresh().await {
+            debug!("Couldn't refresh semantic tokens: {}", e);
+        }
+    }
+
+    async fn did_change_workspace_folders(&self, params: DidChangeWorkspaceFoldersParams) {
+        let removed_manifests = if let Ok(mut workspace) = self.state.workspace.write() {
+            let mut removed_manifests = Vec::new();
+
+            for folder in &params.event.removed {
+                if let Ok(path) = folder.uri.to_file_path() {
+                    debug!("Workspace folder removed: {:?}", path);
+                    if let Some(removed) = workspace.folders.iter().find(|workspace| workspace.root == path) {
+                        removed_manifests.extend(removed.members.iter().map(|member| member.path.join("Cargo.toml")));
+                    }
+                    workspace.remove_folder(&path);
+                }
+            }
+
+            for folder in &params.event.added {
+                if let Ok(path) = folder.uri.to_file_path() {
+                    debug!("Workspace folder added: {:?}", path);
+                    workspace.load_folder(&path).unwrap_or_else(|e| {
+                        debug!("Workspace couldn't load: {}", e);
+                    });
+                }
+            }
+
+            removed_manifests
+        } else {
+            debug!("workspace is locked");
+            return;
+        };
+
+        for manifest in removed_manifests {
+            if let Ok(uri) = Url::from_file_path(manifest) {
+                self.client.publish_diagnostics(uri, Vec::new(), None).await;
+            }
         }
 
-        debug!("Workspace re-analysis complete.");
+        self.state.index_workspace().await;
+        self.state.refresh_layouts();
+        self.publish_view_diagnostics(None).await;
+        self.publish_manifest_diagnostics().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{open_view, temp_files, test_backend};
+    use tower_lsp::lsp_types::{CodeActionContext, CompletionContext, Position, TextDocumentIdentifier, TextDocumentPositionParams};
+
+    async fn completion_labels(backend: &Backend, uri: &Url, position: Position, context: CompletionContext) -> Vec<String> {
+        let params = CompletionParams {
+            text_document_position: TextDocumentPositionParams::new(TextDocumentIdentifier::new(uri.clone()), position),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: Some(context),
+        };
+
+        match backend.completion(params).await.unwrap() {
+            Some(CompletionResponse::List(list)) => list.items.into_iter().map(|item| item.label).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn offers_no_directives_on_attribute_quotes() {
+        let dir = temp_files(&[
+            ("Cargo.toml", "[package]\nname = \"app\"\n"),
+            ("views/index.rs.html", "<a href=\"\">home</a>\n"),
+        ]);
+        let service = test_backend(dir.path());
+        let backend = service.inner();
+        let uri = open_view(backend, &dir.path().join("views/index.rs.html")).await;
+        let position = Position::new(0, 9);
+
+        let quote = CompletionContext {
+            trigger_kind: CompletionTriggerKind::TRIGGER_CHARACTER,
+            trigger_character: Some("\"".to_string()),
+        };
+        assert!(completion_labels(backend, &uri, position, quote).await.is_empty());
+
+        let invoked = CompletionContext {
+            trigger_kind: CompletionTriggerKind::INVOKED,
+            trigger_character: None,
+        };
+        assert!(
+            completion_labels(backend, &uri, position, invoked)
+                .await
+                .iter()
+                .any(|label| label == "if")
+        );
+    }
+
+    #[tokio::test]
+    async fn quick_fix_reads_the_path_after_non_ascii_text() {
+        let dir = temp_files(&[
+            ("Cargo.toml", "[package]\nname = \"app\"\n"),
+            ("views/card.rs.html", "<p>card</p>\n"),
+            ("views/index.rs.html", "<p>é😀</p> @include(\"crad.rs.html\")\n"),
+        ]);
+        let service = test_backend(dir.path());
+        let backend = service.inner();
+        let uri = open_view(backend, &dir.path().join("views/index.rs.html")).await;
+
+        let summary = backend.state.index.read().unwrap()[&uri.to_file_path().unwrap()].clone();
+        let diagnostics = backend.state.diagnostics(&uri, &summary);
+        let range = diagnostics[0].range;
+        assert_eq!(range, Range::new(Position::new(0, 21), Position::new(0, 33)));
+
+        let params = CodeActionParams {
+            text_document: TextDocumentIdentifier::new(uri.clone()),
+            range,
+            context: CodeActionContext {
+                diagnostics,
+                ..Default::default()
+            },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let actions = backend.code_action(params).await.unwrap().unwrap();
+
+        let [CodeActionOrCommand::CodeAction(action)] = actions.as_slice() else {
+            panic!("expected one quick fix, got {actions:?}");
+        };
+        assert_eq!(action.title, "Change to `card.rs.html`");
+        let edits = &action.edit.as_ref().unwrap().changes.as_ref().unwrap()[&uri];
+        assert_eq!(edits[0].range, range);
     }
 }
@@ -0,0 +1,216 @@
+use crate::app_state::AppState;
+use crate::app_state::diagnostics::CachedDiagnostics;
+use crate::backend::Backend;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::Path;
+use tower_lsp::lsp_types::{
+    Diagnostic, DocumentDiagnosticReport, FullDocumentDiagnosticReport, PreviousResultId, RelatedFullDocumentDiagnosticReport,
+    RelatedUnchangedDocumentDiagnosticReport, UnchangedDocumentDiagnosticReport, Url, WorkspaceDiagnosticReport,
+    WorkspaceDocumentDiagnosticReport, WorkspaceFullDocumentDiagnosticReport, WorkspaceUnchangedDocumentDiagnosticReport,
+};
+use tracing::debug;
+
+impl Backend {
+    pub(crate) fn document_diagnostic_report(&self, uri: &Url, previous_result_id: Option<&str>) -> DocumentDiagnosticReport {
+        let (diagnostics, _) = view_diagnostics(&self.state, uri).unwrap_or_default();
+        let result_id = result_id(&diagnostics);
+
+        if previous_result_id == Some(result_id.as_str()) {
+            return DocumentDiagnosticReport::Unchanged(RelatedUnchangedDocumentDiagnosticReport {
+                related_documents: None,
+                unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport { result_id },
+            });
+        }
+
+        DocumentDiagnosticReport::Full(RelatedFullDocumentDiagnosticReport {
+            related_documents: None,
+            full_document_diagnostic_report: FullDocumentDiagnosticReport {
+                result_id: Some(result_id),
+                items: diagnostics,
+            },
+        })
+    }
+
+    // clients ask again as soon as they get an answer, so a request with nothing new is held until the index changes
+    pub(crate) async fn workspace_diagnostic_report(&self, previous_result_ids: Vec<PreviousResultId>) -> WorkspaceDiagnosticReport {
+        let mut changes = self.state.index_changes.subscribe();
+
+        loop {
+            changes.borrow_and_update();
+
+            let state = self.state.clone();
+            let previous = previous_result_ids.clone();
+            let items = match tokio::task::spawn_blocking(move || workspace_items(&state, &previous)).await {
+                Ok(items) => items,
+                Err(e) => {
+                    debug!("Workspace diagnostics failed: {}", e);
+                    Vec::new()
+                }
+            };
+
+            let changed = items.iter().any(|item| matches!(item, WorkspaceDocumentDiagnosticReport::Full(_)));
+
+            if changed || previous_result_ids.is_empty() || changes.changed().await.is_err() {
+                return WorkspaceDiagnosticReport { items };
+            }
+        }
+    }
+}
+
+fn workspace_items(state: &AppState, previous_result_ids: &[PreviousResultId]) -> Vec<WorkspaceDocumentDiagnosticReport> {
+    let paths = match state.index.read() {
+        Ok(index) => index.keys().cloned().collect::<Vec<_>>(),
+        Err(_) => return Vec::new(),
+    };
+    let uris = paths.iter().filter_map(|path| Url::from_file_path(path).ok()).collect::<Vec<_>>();
+
+    let mut items = paths
+        .iter()
+        .zip(&uris)
+        .filter_map(|(path, uri)| {
+            let (diagnostics, version, result_id) = cached_diagnostics(state, path, uri)?;
+
+            let previous = previous_result_ids.iter().find(|previous| previous.uri == *uri);
+            let report = if previous.is_some_and(|previous| previous.value == result_id) {
+                WorkspaceDocumentDiagnosticReport::Unchanged(WorkspaceUnchangedDocumentDiagnosticReport {
+                    uri: uri.clone(),
+                    version,
+                    unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport { result_id },
+                })
+            } else {
+                full_report(uri.clone(), version, diagnostics, result_id)
+            };
+
+            Some(report)
+        })
+        .collect::<Vec<_>>();
+
+    // views that left the index lose their diagnostics, once
+    let cleared = result_id(&[]);
+    items.extend(
+        previous_result_ids
+            .iter()
+            .filter(|previous| !uris.contains(&previous.uri) && previous.value != cleared)
+            .map(|previous| full_report(previous.uri.clone(), None, Vec::new(), cleared.clone())),
+    );
+
+    items
+}
+
+fn full_report(uri: Url, version: Option<i64>, diagnostics: Vec<Diagnostic>, result_id: String) -> WorkspaceDocumentDiagnosticReport {
+    WorkspaceDocumentDiagnosticReport::Full(WorkspaceFullDocumentDiagnosticReport {
+        uri,
+        version,
+        full_document_diagnostic_report: FullDocumentDiagnosticReport {
+            result_id: Some(result_id),
+            items: diagnostics,
+        },
+    })
+}
+
+// only the files that changed since the last request, or read one that did, are checked again
+fn cached_diagnostics(state: &AppState, path: &Path, uri: &Url) -> Option<(Vec<Diagnostic>, Option<i64>, String)> {
+    if let Ok(cache) = state.diagnostic_cache.lock()
+        && let Some(cached) = cache.get(path)
+    {
+        return Some((cached.diagnostics.clone(), cached.version, cached.result_id.clone()));
+    }
+
+    let generation = *state.index_changes.borrow();
+    let (diagnostics, version) = view_diagnostics(state, uri)?;
+    let result_id = result_id(&diagnostics);
+
+    // a change that came in while checking already made these diagnostics stale
+    if let Ok(mut cache) = state.diagnostic_cache.lock()
+        && *state.index_changes.borrow() == generation
+    {
+        let cached = CachedDiagnostics {
+            diagnostics: diagnostics.clone(),
+            version,
+            result_id: result_id.clone(),
+        };
+        cache.insert(path.to_path_buf(), cached);
+    }
+
+    Some((diagnostics, version, result_id))
+}
+
+fn view_diagnostics(state: &AppState, uri: &Url) -> Option<(Vec<Diagnostic>, Option<i64>)> {
+    if let Ok(views) = state.views.read()
+        && let Some(view) = views.get(&uri.to_string())
+    {
+        let summary = state.view_summary(uri, view);
+        return Some((state.diagnostics(uri, &summary), Some(view.version as i64)));
+    }
+
+    let summary = {
+        let index = state.index.read().ok()?;
+        index.get(&uri.to_file_path().ok()?)?.clone()
+    };
+    Some((state.diagnostics(uri, &summary), None))
+}
+
+fn result_id(diagnostics: &[Diagnostic]) -> String {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(diagnostics).unwrap_or_default().hash(&mut hasher);
+    format!("{:x}", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::index::ViewSummary;
+    use crate::test_utils::temp_files;
+    use tower_lsp::lsp_types::Range;
+
+    #[test]
+    fn checks_only_changed_views_and_their_dependents_again() {
+        let dir = temp_files(&[
+            ("Cargo.toml", "[package]\nname = \"app\"\n"),
+            ("views/card.rs.html", ""),
+            ("views/page.rs.html", ""),
+            ("views/other.rs.html", ""),
+        ]);
+        let path = |name: &str| dir.path().join("views").join(name);
+        let uri = |name: &str| Url::from_file_path(path(name)).unwrap();
+
+        let state = AppState::setup();
+        state.workspace.write().unwrap().load_folder(dir.path()).unwrap();
+        let include = ViewSummary {
+            path_directives: vec![("include_directive", Some("card.rs.html".to_string()), Range::default())],
+            ..Default::default()
+        };
+        state.update_index(&uri("page.rs.html"), include);
+        state.update_index(&uri("card.rs.html"), ViewSummary::default());
+        state.update_index(&uri("other.rs.html"), ViewSummary::default());
+
+        let items = workspace_items(&state, &[]);
+        assert_eq!(items.len(), 3);
+        assert_eq!(state.diagnostic_cache.lock().unwrap().len(), 3);
+
+        state.update_index(&uri("card.rs.html"), ViewSummary::default());
+        let cached = state.diagnostic_cache.lock().unwrap().keys().cloned().collect::<Vec<_>>();
+        assert_eq!(cached, [path("other.rs.html")]);
+
+        let previous = items
+            .iter()
+            .map(|item| match item {
+                WorkspaceDocumentDiagnosticReport::Full(report) => PreviousResultId {
+                    uri: report.uri.clone(),
+                    value: report.full_document_diagnostic_report.result_id.clone().unwrap(),
+                },
+                WorkspaceDocumentDiagnosticReport::Unchanged(report) => PreviousResultId {
+                    uri: report.uri.clone(),
+                    value: report.unchanged_document_diagnostic_report.result_id.clone(),
+                },
+            })
+            .collect::<Vec<_>>();
+        let items = workspace_items(&state, &previous);
+        assert!(
+            items
+                .iter()
+                .all(|item| matches!(item, WorkspaceDocumentDiagnosticReport::Unchanged(_)))
+        );
+        assert_eq!(state.diagnostic_cache.lock().unwrap().len(), 3);
+    }
+}
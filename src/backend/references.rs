@@ -0,0 +1,71 @@
+use crate::app_state::view::View;
+use crate::backend::Backend;
+use crate::backend::rename::RenameTarget;
+use crate::backend::tree_extensions::TreeExtensions;
+use std::path::{Path, PathBuf};
+use tower_lsp::lsp_types::{Location, Range, Url};
+use tree_sitter::Tree;
+
+impl Backend {
+    pub(crate) fn find_references(&self, uri: &Url, view: &View, byte_offset: usize, include_declaration: bool) -> Vec<Location> {
+        let Ok(file_path) = uri.to_file_path() else {
+            return Vec::new();
+        };
+
+        let mut locations = match self.rename_target(uri, view, byte_offset) {
+            Some((RenameTarget::Component(name), _, _)) => self.component_locations(&file_path, view, &name, include_declaration),
+            Some((RenameTarget::Section(name, layout_path), _, _)) => {
+                self.state.section_locations(&file_path, &name, layout_path.as_deref())
+            }
+            Some((RenameTarget::File(target_path, _), _, _)) => self.state.path_references(&target_path),
+            None => {
+                let target_path = self.directive_target(&file_path, view, byte_offset).unwrap_or(file_path);
+                self.state.path_references(&target_path)
+            }
+        };
+
+        locations.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.start.cmp(&b.1.start)));
+
+        locations
+            .into_iter()
+            .filter_map(|(path, range)| Some(Location::new(Url::from_file_path(path).ok()?, range)))
+            .collect()
+    }
+
+    fn component_locations(&self, file_path: &Path, view: &View, name: &str, include_declaration: bool) -> Vec<(PathBuf, Range)> {
+        let mut locations = Vec::new();
+
+        if include_declaration {
+            locations.extend(
+                view.tree
+                    .find_use_locations(&self.state.language, &view.source)
+                    .into_iter()
+                    .filter(|(path, alias, _)| View::use_directive_name(path, alias.as_deref()) == Some(name))
+                    .map(|(_, _, range)| (file_path.to_path_buf(), range)),
+            );
+        }
+
+        if let Ok(index) = self.state.index.read()
+            && let Some(summary) = index.get(file_path)
+        {
+            locations.extend(
+                summary
+                    .components
+                    .iter()
+                    .filter(|(component, _)| component == name)
+                    .map(|(_, range)| (file_path.to_path_buf(), *range)),
+            );
+        }
+
+        locations
+    }
+
+    fn directive_target(&self, file_path: &Path, view: &View, byte_offset: usize) -> Option<PathBuf> {
+        let node = view.tree.find_ancestor(byte_offset, &["include_directive", "extends_directive"])?;
+        let path = node
+            .child_by_field_name("path")
+            .and_then(|path| Tree::string_value(path, &view.source));
+
+        self.state.resolve_directive_path(file_path, node.kind(), path.as_deref())
+    }
+}
@@ -0,0 +1,313 @@
+use crate::app_state::view::View;
+use crate::backend::Backend;
+use crate::backend::tree_extensions::TreeExtensions;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tower_lsp::jsonrpc::{Error, Result};
+use tower_lsp::lsp_types::{
+    DocumentChangeOperation, DocumentChanges, OneOf, OptionalVersionedTextDocumentIdentifier, Range, RenameFile, ResourceOp,
+    TextDocumentEdit, TextEdit, Url, WorkspaceEdit,
+};
+use tracing::error;
+use tree_sitter::{Node, Tree};
+
+pub const RENAME_KINDS: [&str; 6] = [
+    "component_tag_identifier",
+    "as_clause",
+    "section_directive",
+    "section_block",
+    "render_directive",
+    "use_directive",
+];
+
+pub enum RenameTarget {
+    Component(String),
+    Section(String, Option<PathBuf>),
+    // the file and the folder its path was resolved from
+    File(PathBuf, PathBuf),
+}
+
+impl Backend {
+    pub(crate) fn rename_target(&self, uri: &Url, view: &View, byte_offset: usize) -> Option<(RenameTarget, Range, String)> {
+        let node = view.tree.find_ancestor(byte_offset, &RENAME_KINDS)?;
+        let contains = |node: &Node| node.start_byte() <= byte_offset && byte_offset <= node.end_byte();
+
+        match node.kind() {
+            "component_tag_identifier" => {
+                let name = Tree::string_value(node, &view.source)?;
+                view.use_directive_path(&name)?;
+                Some((
+                    RenameTarget::Component(name.clone()),
+                    Tree::from_range(node.range(), &view.source),
+                    name,
+                ))
+            }
+            "as_clause" => {
+                let alias = node.child_by_field_name("alias").filter(contains)?;
+                let name = Tree::string_value(alias, &view.source)?;
+                Some((
+                    RenameTarget::Component(name.clone()),
+                    Tree::from_range(alias.range(), &view.source),
+                    name,
+                ))
+            }
+            "section_directive" | "section_block" => {
+                let name_node = node.child_by_field_name("name").filter(contains)?;
+                let name = Tree::string_value(name_node, &view.source)?;
+                let target = RenameTarget::Section(name.clone(), view.layout_path.clone());
+                Some((target, Tree::string_range(name_node, &view.source), name))
+            }
+            "render_directive" => {
+                let name_node = Tree::render_name(node).filter(contains)?;
+                let name = Tree::string_value(name_node, &view.source)?;
+                let target = RenameTarget::Section(name.clone(), uri.to_file_path().ok());
+                Some((target, Tree::string_range(name_node, &view.source), name))
+            }
+            "use_directive" => {
+                let path_node = node.child_by_field_name("path").filter(contains)?;
+                let path = Tree::string_value(path_node, &view.source)?;
+                let file_path = self.state.find_view(uri, &path)?;
+                let base = PathBuf::from(file_path.to_string_lossy().strip_suffix(path.as_str())?);
+                Some((
+                    RenameTarget::File(file_path, base),
+                    Tree::string_range(path_node, &view.source),
+                    path,
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn rename_edit(&self, uri: &Url, view: &View, target: RenameTarget, new_name: &str) -> Result<Option<WorkspaceEdit>> {
+        let new_name = new_name.trim();
+        if new_name.is_empty() || new_name.contains(char::is_whitespace) {
+            return Err(Error::invalid_params(format!("`{new_name}` is not a valid name")));
+        }
+
+        match target {
+            RenameTarget::Component(name) => {
+                let edits = self.component_rename_edits(view, &name, new_name);
+                Ok(Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(uri.clone(), edits)])),
+                    ..Default::default()
+                }))
+            }
+            RenameTarget::Section(name, layout_path) => Ok(self.section_rename_edit(uri, &name, layout_path, new_name)),
+            RenameTarget::File(file_path, base) => self.file_rename_edit(&file_path, &base, new_name),
+        }
+    }
+
+    fn component_rename_edits(&self, view: &View, name: &str, new_name: &str) -> Vec<TextEdit> {
+        let mut edits = view
+            .tree
+            .find(&self.state.language, "(component_tag_identifier) @name", &view.source, |x| {
+                let node = x.captures.first()?.node;
+                (Tree::string_value(node, &view.source)? == name).then(|| TextEdit {
+                    range: Tree::from_range(node.range(), &view.source),
+                    new_text: new_name.to_string(),
+                })
+            })
+            .unwrap_or_else(|x| {
+                error!("Error during component rename query: {}", x);
+                vec![]
+            });
+
+        let use_edits = view
+            .tree
+            .find(
+                &self.state.language,
+                "(use_directive path: (string_line)) @directive",
+                &view.source,
+                |x| {
+                    let node = x.captures.first()?.node;
+                    let path_node = node.child_by_field_name("path")?;
+                    let path = Tree::string_value(path_node, &view.source)?;
+
+                    let mut cursor = node.walk();
+                    let alias_node = node
+                        .named_children(&mut cursor)
+                        .find(|child| child.kind() == "as_clause")
+                        .and_then(|as_clause| as_clause.child_by_field_name("alias"));
+                    let alias = alias_node.and_then(|alias| Tree::string_value(alias, &view.source));
+
+                    if View::use_directive_name(&path, alias.as_deref())? != name {
+                        return None;
+                    }
+
+                    // a use without alias takes its name from the file, so the new name becomes an alias
+                    Some(match alias_node {
+                        Some(alias_node) => TextEdit {
+                            range: Tree::from_range(alias_node.range(), &view.source),
+                            new_text: new_name.to_string(),
+                        },
+                        None => {
+                            let end = Tree::from_range(path_node.range(), &view.source).end;
+                            TextEdit {
+                                range: Range { start: end, end },
+                                new_text: format!(" as {new_name}"),
+                            }
+                        }
+                    })
+                },
+            )
+            .unwrap_or_else(|x| {
+                error!("Error during use rename query: {}", x);
+                vec![]
+            });
+
+        edits.extend(use_edits);
+        edits
+    }
+
+    fn section_rename_edit(&self, uri: &Url, name: &str, layout_path: Option<PathBuf>, new_name: &str) -> Option<WorkspaceEdit> {
+        let file_path = uri.to_file_path().ok()?;
+        let locations = self.state.section_locations(&file_path, name, layout_path.as_deref());
+
+        Some(WorkspaceEdit {
+            changes: Some(Self::location_edits(locations, new_name)),
+            ..Default::default()
+        })
+    }
+
+    pub(crate) fn location_edits(locations: Vec<(PathBuf, Range)>, new_text: &str) -> HashMap<Url, Vec<TextEdit>> {
+        let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+
+        for (path, range) in locations {
+            if let Ok(uri) = Url::from_file_path(path) {
+                changes.entry(uri).or_default().push(TextEdit {
+                    range,
+                    new_text: new_text.to_string(),
+                });
+            }
+        }
+
+        changes
+    }
+
+    // the new name is read from the same folder as the old path, every reference gets its own new path
+    fn file_rename_edit(&self, file_path: &Path, base: &Path, new_name: &str) -> Result<Option<WorkspaceEdit>> {
+        let new_path = base.join(new_name);
+        if new_path.exists() {
+            return Err(Error::invalid_params(format!("`{new_name}` already exists")));
+        }
+
+        let (Ok(old_uri), Ok(new_uri)) = (Url::from_file_path(file_path), Url::from_file_path(&new_path)) else {
+            return Ok(None);
+        };
+
+        let changes = self.moved_file_edits(&[(file_path.to_path_buf(), new_path)]);
+
+        // without resource operations the references are still updated, the file is left to the client
+        if !self.state.rename_file_support() {
+            return Ok(Some(WorkspaceEdit {
+                changes: Some(changes),
+                ..Default::default()
+            }));
+        }
+
+        let mut operations = changes
+            .into_iter()
+            .map(|(uri, edits)| {
+                DocumentChangeOperation::Edit(TextDocumentEdit {
+                    text_document: OptionalVersionedTextDocumentIdentifier { uri, version: None },
+                    edits: edits.into_iter().map(OneOf::Left).collect(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        operations.push(DocumentChangeOperation::Op(ResourceOp::Rename(RenameFile {
+            old_uri,
+            new_uri,
+            options: None,
+            annotation_id: None,
+        })));
+
+        Ok(Some(WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Operations(operations)),
+            ..Default::default()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{open_view, temp_files, test_backend};
+    use tower_lsp::lsp_types::{
+        ClientCapabilities, Position, ResourceOperationKind, WorkspaceClientCapabilities, WorkspaceEditClientCapabilities,
+    };
+
+    #[tokio::test]
+    async fn renames_folder_relative_references_after_non_ascii_text() {
+        let dir = temp_files(&[
+            ("Cargo.toml", "[package]\nname = \"app\"\n"),
+            ("views/home.rs.html", "@include(\"pages/card.rs.html\")\n"),
+            ("views/pages/card.rs.html", "<p>card</p>\n"),
+            ("views/pages/index.rs.html", "@use \"card.rs.html\" as Card\n<p>é😀 <Card/></p>\n"),
+        ]);
+        let root = dir.path();
+        let service = test_backend(root);
+        let backend = service.inner();
+        let uri = open_view(backend, &root.join("views/pages/index.rs.html")).await;
+
+        let views = backend.state.views.read().unwrap();
+        let view = &views[&uri.to_string()];
+
+        let offset = view.source.find("Card/>").unwrap();
+        let (target, range, _) = backend.rename_target(&uri, view, offset).unwrap();
+        assert_eq!(range, Range::new(Position::new(1, 8), Position::new(1, 12)));
+        let edit = backend.rename_edit(&uri, view, target, "Panel").unwrap().unwrap();
+        let edits = &edit.changes.unwrap()[&uri];
+        assert!(edits.iter().any(|edit| edit.range == range && edit.new_text == "Panel"));
+
+        // a client without resource operations only gets the text edits
+        let offset = view.source.find("card.rs.html").unwrap();
+        let (target, ..) = backend.rename_target(&uri, view, offset).unwrap();
+        let edit = backend.rename_edit(&uri, view, target, "panel.rs.html").unwrap().unwrap();
+        assert!(edit.document_changes.is_none());
+        assert_eq!(edit.changes.unwrap()[&uri][0].new_text, "panel.rs.html");
+
+        *backend.state.client_capabilities.write().unwrap() = ClientCapabilities {
+            workspace: Some(WorkspaceClientCapabilities {
+                workspace_edit: Some(WorkspaceEditClientCapabilities {
+                    resource_operations: Some(vec![ResourceOperationKind::Rename]),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let (target, ..) = backend.rename_target(&uri, view, offset).unwrap();
+        let edit = backend.rename_edit(&uri, view, target, "panel.rs.html").unwrap().unwrap();
+        let Some(DocumentChanges::Operations(operations)) = edit.document_changes else {
+            panic!("expected document change operations");
+        };
+
+        let mut texts = Vec::new();
+        let mut renamed = None;
+        for operation in operations {
+            match operation {
+                DocumentChangeOperation::Edit(edit) => {
+                    let path = edit.text_document.uri.to_file_path().unwrap();
+                    for edit in edit.edits {
+                        if let OneOf::Left(edit) = edit {
+                            texts.push((path.strip_prefix(root).unwrap().to_path_buf(), edit.new_text));
+                        }
+                    }
+                }
+                DocumentChangeOperation::Op(ResourceOp::Rename(rename)) => renamed = Some(rename.new_uri.to_file_path().unwrap()),
+                DocumentChangeOperation::Op(_) => {}
+            }
+        }
+        texts.sort();
+
+        assert_eq!(
+            texts,
+            [
+                (PathBuf::from("views/home.rs.html"), "pages/panel.rs.html".to_string()),
+                (PathBuf::from("views/pages/index.rs.html"), "panel.rs.html".to_string()),
+            ]
+        );
+        assert_eq!(renamed, Some(root.join("views/pages/panel.rs.html")));
+    }
+}
@@ -1,7 +1,9 @@
 use crate::consts::{SEMANTIC_TOKEN_MODIFIERS, SEMANTIC_TOKEN_TYPES};
+use std::path::PathBuf;
 use tower_lsp::lsp_types::{
-    OneOf, SemanticTokensFullOptions, SemanticTokensLegend, SemanticTokensOptions, SemanticTokensServerCapabilities,
-    WorkDoneProgressOptions, WorkspaceFoldersServerCapabilities, WorkspaceServerCapabilities,
+    FileOperationFilter, FileOperationPattern, FileOperationPatternKind, FileOperationRegistrationOptions, OneOf,
+    SemanticTokensFullOptions, SemanticTokensLegend, SemanticTokensOptions, SemanticTokensServerCapabilities, WorkDoneProgressOptions,
+    WorkspaceFileOperationsServerCapabilities, WorkspaceFoldersServerCapabilities, WorkspaceServerCapabilities,
 };
 
 pub fn semantic_tokens_capabilities() -> Option<SemanticTokensServerCapabilities> {
@@ -18,13 +20,35 @@ pub fn semantic_tokens_capabilities() -> Option<SemanticTokensServerCapabilities
     }))
 }
 
-pub fn workspace_capabilities() -> Option<WorkspaceServerCapabilities> {
+// folder renames are only asked for inside the view roots, the folders of other files don't need edits
+pub fn workspace_capabilities(view_roots: &[PathBuf]) -> Option<WorkspaceServerCapabilities> {
+    let mut filters = vec![file_operation_filter("**/*.rs.html", FileOperationPatternKind::File)];
+    for root in view_roots {
+        let root = root.to_string_lossy().replace('\\', "/");
+        filters.push(file_operation_filter(&root, FileOperationPatternKind::Folder));
+        filters.push(file_operation_filter(&format!("{root}/**"), FileOperationPatternKind::Folder));
+    }
+
     Some(WorkspaceServerCapabilities {
         workspace_folders: Some(WorkspaceFoldersServerCapabilities {
-            supported: Some(false),
+            supported: Some(true),
             change_notifications: Some(OneOf::Left(true)),
         }),
 
-        file_operations: None,
+        file_operations: Some(WorkspaceFileOperationsServerCapabilities {
+            will_rename: Some(FileOperationRegistrationOptions { filters }),
+            ..Default::default()
+        }),
     })
 }
+
+fn file_operation_filter(glob: &str, matches: FileOperationPatternKind) -> FileOperationFilter {
+    FileOperationFilter {
+        scheme: Some("file".to_string()),
+        pattern: FileOperationPattern {
+            glob: glob.to_string(),
+            matches: Some(matches),
+            options: None,
+        },
+    }
+}
@@ -1,7 +1,7 @@
 use tower_lsp::lsp_types;
 use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity};
 use tracing::error;
-use tree_sitter::{Language, Query, QueryCursor, QueryMatch, Range, StreamingIterator};
+use tree_sitter::{Language, Node, Point, Query, QueryCursor, QueryMatch, Range, StreamingIterator};
 
 pub trait TreeExtensions {
     const STRING_TRIMS: &'_ [char] = &[' ', '\'', '"'];
@@ -14,24 +14,101 @@ pub trait TreeExtensions {
 
     fn find_uses(&self, language: &Language, source: &str) -> Vec<(String, Option<String>)>;
 
+    fn find_use_locations(&self, language: &Language, source: &str) -> Vec<(String, Option<String>, lsp_types::Range)>;
+
     fn find_extends(&self, language: &Language, source: &str) -> Option<Option<String>>;
 
-    fn find_sections(&self, language: &Language, source: &str) -> Vec<String>;
+    fn find_sections(&self, language: &Language, source: &str) -> Vec<(String, lsp_types::Range)>;
+
+    fn find_components(&self, language: &Language, source: &str) -> Vec<(String, lsp_types::Range)>;
+
+    fn find_component_tags(&self, language: &Language, source: &str) -> Vec<(String, Vec<String>, lsp_types::Range)>;
+
+    fn component_parameter_names(component_tag: Node, source: &str) -> Vec<String> {
+        let mut cursor = component_tag.walk();
+        component_tag
+            .named_children(&mut cursor)
+            .filter(|child| child.kind() == "component_tag_parameter")
+            .filter_map(|child| Self::string_value(child.child_by_field_name("name")?, source))
+            .collect()
+    }
+
+    fn find_path_directives(&self, language: &Language, source: &str) -> Vec<(&'static str, Option<String>, lsp_types::Range)>;
 
     fn find_error(&self, language: &Language, source: &str) -> Vec<Diagnostic>;
 
-    fn from_range(range: Range) -> lsp_types::Range {
-        let start_point = range.start_point;
-        let end_point = range.end_point;
+    fn find_renders(&self, language: &Language, source: &str) -> Vec<(String, lsp_types::Range)>;
+
+    fn find_ancestor(&self, byte_offset: usize, kinds: &[&str]) -> Option<Node<'_>>;
+
+    fn render_name(node: Node) -> Option<Node> {
+        let mut cursor = node.walk();
+        node.named_children(&mut cursor)
+            .find(|child| matches!(child.kind(), "string_line" | "rust_identifier"))
+    }
+
+    // the head of an `@if`, `@for`, `@while` or `@match`, without the keyword when the grammar keeps it there
+    fn statement_head(node: Node, source: &str, keyword: &str) -> Option<String> {
+        let head = node.child_by_field_name("head")?.utf8_text(source.as_bytes()).ok()?.trim();
+        if head.is_empty() {
+            return None;
+        }
+
+        Some(
+            head.strip_prefix(keyword)
+                .filter(|rest| rest.starts_with(char::is_whitespace))
+                .unwrap_or(head)
+                .trim()
+                .to_string(),
+        )
+    }
+
+    fn string_value(node: Node, source: &str) -> Option<String> {
+        Some(
+            node.utf8_text(source.as_bytes())
+                .ok()?
+                .trim()
+                .trim_matches(Self::STRING_TRIMS)
+                .to_string(),
+        )
+    }
+
+    fn string_range(node: Node, source: &str) -> lsp_types::Range {
+        let mut range = Self::from_range(node.range(), source);
+        let Ok(text) = node.utf8_text(source.as_bytes()) else {
+            return range;
+        };
+
+        let start = text.trim_start();
+        let end = text.trim_end();
+        let leading = text.len() - start.len() + start.starts_with(['"', '\'']) as usize;
+        let trailing = text.len() - end.len() + end.ends_with(['"', '\'']) as usize;
+
+        if range.start.line == range.end.line && leading + trailing <= text.len() {
+            range.start.character += text[..leading].encode_utf16().count() as u32;
+            range.end.character -= text[text.len() - trailing..].encode_utf16().count() as u32;
+        }
+
+        range
+    }
+
+    fn from_range(range: Range, source: &str) -> lsp_types::Range {
         lsp_types::Range {
-            start: lsp_types::Position {
-                line: start_point.row as u32,
-                character: start_point.column as u32,
-            },
-            end: lsp_types::Position {
-                line: end_point.row as u32,
-                character: end_point.column as u32,
-            },
+            start: Self::from_point(range.start_point, range.start_byte, source),
+            end: Self::from_point(range.end_point, range.end_byte, source),
+        }
+    }
+
+    // tree-sitter columns count bytes, lsp characters count utf-16 units
+    fn from_point(point: Point, byte_offset: usize, source: &str) -> lsp_types::Position {
+        let line_start = byte_offset.saturating_sub(point.column);
+        let character = source
+            .get(line_start..byte_offset)
+            .map_or(point.column, |line| line.encode_utf16().count());
+
+        lsp_types::Position {
+            line: point.row as u32,
+            character: character as u32,
         }
     }
 }
@@ -79,24 +156,27 @@ impl TreeExtensions for tree_sitter::Tree {
     }
 
     fn find_uses(&self, language: &Language, source: &str) -> Vec<(String, Option<String>)> {
-        let query_str = "(use_directive path: (string_line) @use_path (as_clause alias: (rust_identifier) @use_alias)?)";
+        self.find_use_locations(language, source)
+            .into_iter()
+            .map(|(use_path, use_alias, _)| (use_path, use_alias))
+            .collect()
+    }
+
+    fn find_use_locations(&self, language: &Language, source: &str) -> Vec<(String, Option<String>, lsp_types::Range)> {
+        let query_str = "(use_directive path: (string_line)) @directive";
         self.find(language, query_str, source, |x| {
-            let mut captures = x.captures.iter();
-            let use_path = captures
-                .next()?
-                .node
-                .utf8_text(source.as_bytes())
-                .ok()?
-                .trim()
-                .trim_matches(Self::STRING_TRIMS)
-                .to_string();
+            let node = x.captures.first()?.node;
+            let use_path = Self::string_value(node.child_by_field_name("path")?, source)?;
 
-            let use_alias = captures
-                .next()
-                .and_then(|x| x.node.utf8_text(source.as_bytes()).ok())
-                .map(|x| x.trim().to_string());
+            let mut cursor = node.walk();
+            let use_alias = node
+                .named_children(&mut cursor)
+                .find(|child| child.kind() == "as_clause")
+                .and_then(|as_clause| as_clause.child_by_field_name("alias"))
+                .and_then(|alias| alias.utf8_text(source.as_bytes()).ok())
+                .map(|alias| alias.trim().to_string());
 
-            Some((use_path, use_alias))
+            Some((use_path, use_alias, Self::from_range(node.range(), source)))
         })
         .unwrap_or_else(|x| {
             error!("Error during use_path query: {}", x);
@@ -124,22 +204,65 @@ impl TreeExtensions for tree_sitter::Tree {
         .pop()?
     }
 
-    fn find_sections(&self, language: &Language, source: &str) -> Vec<String> {
+    fn find_sections(&self, language: &Language, source: &str) -> Vec<(String, lsp_types::Range)> {
         let query_str = "[(section_directive name: (string_line) @name) (section_block name: (rust_identifier) @name)]";
         self.find(language, query_str, source, |x| {
-            let section_name = x
-                .captures
-                .first()?
-                .node
-                .utf8_text(source.as_bytes())
-                .ok()?
-                .trim_matches(Self::STRING_TRIMS)
-                .to_string();
+            let node = x.captures.first()?.node;
+            let section_name = node.utf8_text(source.as_bytes()).ok()?.trim_matches(Self::STRING_TRIMS).to_string();
 
-            Some(section_name)
+            Some((section_name, Self::string_range(node, source)))
         })
         .unwrap_or_else(|x| {
-            error!("Error during include path query: {}", x);
+            error!("Error during section query: {}", x);
+            vec![]
+        })
+    }
+
+    fn find_components(&self, language: &Language, source: &str) -> Vec<(String, lsp_types::Range)> {
+        let query_str = "(component_tag name: (component_tag_identifier) @name)";
+        self.find(language, query_str, source, |x| {
+            let node = x.captures.first()?.node;
+            Some((Self::string_value(node, source)?, Self::from_range(node.range(), source)))
+        })
+        .unwrap_or_else(|x| {
+            error!("Error during component query: {}", x);
+            vec![]
+        })
+    }
+
+    fn find_component_tags(&self, language: &Language, source: &str) -> Vec<(String, Vec<String>, lsp_types::Range)> {
+        let query_str = "(component_tag name: (component_tag_identifier) @name) @tag";
+        self.find(language, query_str, source, |x| {
+            let tag = x.captures.iter().find(|capture| capture.node.kind() == "component_tag")?.node;
+            let name_node = tag.child_by_field_name("name")?;
+            let name = Self::string_value(name_node, source)?;
+
+            Some((
+                name,
+                Self::component_parameter_names(tag, source),
+                Self::from_range(name_node.range(), source),
+            ))
+        })
+        .unwrap_or_else(|x| {
+            error!("Error during component tag query: {}", x);
+            vec![]
+        })
+    }
+
+    fn find_path_directives(&self, language: &Language, source: &str) -> Vec<(&'static str, Option<String>, lsp_types::Range)> {
+        let query_str = "[(include_directive) (use_directive) (extends_directive)] @directive";
+        self.find(language, query_str, source, |x| {
+            let node = x.captures.first()?.node;
+
+            let (path, range) = match node.child_by_field_name("path") {
+                Some(path_node) => (Self::string_value(path_node, source), Self::string_range(path_node, source)),
+                None => (None, Self::from_range(node.range(), source)),
+            };
+
+            Some((node.kind(), path, range))
+        })
+        .unwrap_or_else(|x| {
+            error!("Error during directive path query: {}", x);
             vec![]
         })
     }
@@ -155,7 +278,7 @@ impl TreeExtensions for tree_sitter::Tree {
                 node.range()
             };
 
-            let range = Self::from_range(range);
+            let range = Self::from_range(range, source);
             let severity = Some(DiagnosticSeverity::ERROR);
 
             let message = if node.is_missing() {
@@ -178,4 +301,51 @@ impl TreeExtensions for tree_sitter::Tree {
             vec![]
         })
     }
+
+    fn find_renders(&self, language: &Language, source: &str) -> Vec<(String, lsp_types::Range)> {
+        let query_str = "(render_directive) @directive";
+        self.find(language, query_str, source, |x| {
+            let name_node = Self::render_name(x.captures.first()?.node)?;
+            Some((Self::string_value(name_node, source)?, Self::string_range(name_node, source)))
+        })
+        .unwrap_or_else(|x| {
+            error!("Error during render query: {}", x);
+            vec![]
+        })
+    }
+
+    fn find_ancestor(&self, byte_offset: usize, kinds: &[&str]) -> Option<Node<'_>> {
+        let mut node = self.root_node().descendant_for_byte_range(byte_offset, byte_offset)?;
+
+        loop {
+            if kinds.contains(&node.kind()) {
+                return Some(node);
+            }
+
+            node = node.parent()?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tree_sitter::{Parser, Tree};
+
+    #[test]
+    fn converts_byte_columns_to_utf16() {
+        let source = "<p>\n<b>é😀 <i>x</i></b>\n</p>\n";
+        let mut parser = Parser::new();
+        parser.set_language(&Language::new(tree_sitter_html::LANGUAGE)).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+
+        let start = source.find("<i>").unwrap();
+        let end = source.find("</b>").unwrap();
+        let node = tree.root_node().descendant_for_byte_range(start, end).unwrap();
+        assert_eq!(node.range().start_point.column, 10);
+
+        let range = Tree::from_range(node.range(), source);
+        assert_eq!((range.start.line, range.start.character), (1, 7));
+        assert_eq!((range.end.line, range.end.character), (1, 15));
+    }
 }
@@ -0,0 +1,21 @@
+pub mod check;
+pub mod fmt;
+
+use clap::Subcommand;
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Check every view in the workspace and report its diagnostics
+    Check(check::CheckArgs),
+    /// Format views in place, or show what would change with --check
+    Fmt(fmt::FmtArgs),
+}
+
+impl Command {
+    pub fn run(self) -> i32 {
+        match self {
+            Command::Check(args) => check::run(args),
+            Command::Fmt(args) => fmt::run(args),
+        }
+    }
+}
@@ -0,0 +1,407 @@
+use crate::app_state::AppState;
+use clap::{Args, ValueEnum};
+use serde_json::{Value, json};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Url};
+
+#[derive(Args)]
+pub struct CheckArgs {
+    /// Directory of the Cargo workspace or package
+    #[arg(default_value = ".")]
+    path: PathBuf,
+
+    /// How the diagnostics are printed, lines and columns count from one and columns count characters
+    #[arg(long, value_enum, default_value_t = OutputFormat::Human)]
+    format: OutputFormat,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Human,
+    Json,
+    Github,
+    Sarif,
+}
+
+struct Problem {
+    path: String,
+    diagnostic: Diagnostic,
+    location: (u32, u32, u32, u32),
+}
+
+impl Problem {
+    fn new(path: String, source: &str, diagnostic: Diagnostic) -> Self {
+        let location = location(source, &diagnostic);
+        Self {
+            path,
+            diagnostic,
+            location,
+        }
+    }
+}
+
+pub fn run(args: CheckArgs) -> i32 {
+    let root = match args.path.canonicalize() {
+        Ok(root) => root,
+        Err(e) => {
+            eprintln!("Couldn't open {}: {}", args.path.display(), e);
+            return 2;
+        }
+    };
+
+    let state = AppState::setup();
+    let problems = match check(&state, &root) {
+        Ok(problems) => problems,
+        Err(e) => {
+            eprintln!("Couldn't load workspace: {}", e);
+            return 2;
+        }
+    };
+
+    match args.format {
+        OutputFormat::Human => print_human(&problems),
+        OutputFormat::Json => println!("{}", json_report(&problems)),
+        OutputFormat::Github => print_github(&problems),
+        OutputFormat::Sarif => println!("{}", sarif_report(&problems)),
+    }
+
+    exit_code(&problems)
+}
+
+fn exit_code(problems: &[Problem]) -> i32 {
+    i32::from(problems.iter().any(|problem| is_error(&problem.diagnostic)))
+}
+
+fn check(state: &AppState, root: &Path) -> Result<Vec<Problem>, String> {
+    if let Ok(mut workspace) = state.workspace.write() {
+        workspace.load_folder(root)?;
+    }
+
+    state.index_workspace_blocking();
+
+    let (manifests, files) = match state.workspace.read() {
+        Ok(workspace) => {
+            let manifests = workspace
+                .members()
+                .map(|member| (member.path.join("Cargo.toml"), member.manifest_diagnostics.clone()))
+                .collect::<Vec<_>>();
+            (manifests, workspace.view_files())
+        }
+        Err(_) => return Err("workspace is locked".to_string()),
+    };
+
+    let display_path = |path: &Path| path.strip_prefix(root).unwrap_or(path).to_string_lossy().replace('\\', "/");
+
+    let mut problems = Vec::new();
+    for (path, diagnostics) in manifests {
+        let source = fs::read_to_string(&path).unwrap_or_default();
+        problems.extend(
+            state
+                .filter_diagnostics(diagnostics)
+                .into_iter()
+                .map(|diagnostic| Problem::new(display_path(&path), &source, diagnostic)),
+        );
+    }
+
+    for path in files {
+        let Ok(uri) = Url::from_file_path(&path) else {
+            continue;
+        };
+        let summary = state.index.read().ok().and_then(|index| index.get(&path).cloned());
+        let Some(summary) = summary else {
+            eprintln!("Couldn't read {}", path.display());
+            continue;
+        };
+
+        let source = fs::read_to_string(&path).unwrap_or_default();
+        problems.extend(
+            state
+                .diagnostics(&uri, &summary)
+                .into_iter()
+                .map(|diagnostic| Problem::new(display_path(&path), &source, diagnostic)),
+        );
+    }
+
+    Ok(problems)
+}
+
+fn is_error(diagnostic: &Diagnostic) -> bool {
+    matches!(diagnostic.severity, None | Some(DiagnosticSeverity::ERROR))
+}
+
+fn severity_name(diagnostic: &Diagnostic) -> &'static str {
+    match diagnostic.severity {
+        Some(DiagnosticSeverity::WARNING) => "warning",
+        Some(DiagnosticSeverity::INFORMATION) => "info",
+        Some(DiagnosticSeverity::HINT) => "hint",
+        _ => "error",
+    }
+}
+
+fn code(diagnostic: &Diagnostic) -> Option<String> {
+    match diagnostic.code.as_ref()? {
+        NumberOrString::String(code) => Some(code.clone()),
+        NumberOrString::Number(code) => Some(code.to_string()),
+    }
+}
+
+// lsp positions are zero based and count utf-16 units, reports count lines and characters from one
+fn location(source: &str, diagnostic: &Diagnostic) -> (u32, u32, u32, u32) {
+    let column = |position: Position| {
+        let Some(line) = source.lines().nth(position.line as usize) else {
+            return position.character + 1;
+        };
+
+        let mut units = 0;
+        let characters = line
+            .chars()
+            .take_while(|c| {
+                let before = units < position.character;
+                units += c.len_utf16() as u32;
+                before
+            })
+            .count();
+
+        characters as u32 + 1
+    };
+
+    let range = diagnostic.range;
+    (range.start.line + 1, column(range.start), range.end.line + 1, column(range.end))
+}
+
+fn print_human(problems: &[Problem]) {
+    for problem in problems {
+        let (line, column, _, _) = problem.location;
+        let code = code(&problem.diagnostic).map(|code| format!("[{code}]")).unwrap_or_default();
+
+        println!(
+            "{}:{}:{}: {}{}: {}",
+            problem.path,
+            line,
+            column,
+            severity_name(&problem.diagnostic),
+            code,
+            problem.diagnostic.message
+        );
+    }
+
+    let errors = problems.iter().filter(|problem| is_error(&problem.diagnostic)).count();
+    let warnings = problems.len() - errors;
+    if problems.is_empty() {
+        println!("No problems found");
+    } else {
+        println!("{} error(s), {} other problem(s)", errors, warnings);
+    }
+}
+
+fn json_report(problems: &[Problem]) -> Value {
+    let problems = problems
+        .iter()
+        .map(|problem| {
+            let (line, column, end_line, end_column) = problem.location;
+            json!({
+                "file": problem.path,
+                "line": line,
+                "column": column,
+                "endLine": end_line,
+                "endColumn": end_column,
+                "severity": severity_name(&problem.diagnostic),
+                "code": code(&problem.diagnostic),
+                "message": problem.diagnostic.message,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    Value::Array(problems)
+}
+
+fn print_github(problems: &[Problem]) {
+    for annotation in github_annotations(problems) {
+        println!("{annotation}");
+    }
+}
+
+fn github_annotations(problems: &[Problem]) -> Vec<String> {
+    let escape_data = |text: &str| text.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A");
+    let escape_property = |text: &str| escape_data(text).replace(':', "%3A").replace(',', "%2C");
+
+    problems
+        .iter()
+        .map(|problem| {
+            let command = match problem.diagnostic.severity {
+                Some(DiagnosticSeverity::WARNING) => "warning",
+                Some(DiagnosticSeverity::INFORMATION | DiagnosticSeverity::HINT) => "notice",
+                _ => "error",
+            };
+            let (line, column, end_line, end_column) = problem.location;
+
+            let mut properties = format!(
+                "file={},line={},col={},endLine={},endColumn={}",
+                escape_property(&problem.path),
+                line,
+                column,
+                end_line,
+                end_column
+            );
+            if let Some(code) = code(&problem.diagnostic) {
+                properties.push_str(&format!(",title={}", escape_property(&code)));
+            }
+
+            format!("::{} {}::{}", command, properties, escape_data(&problem.diagnostic.message))
+        })
+        .collect()
+}
+
+fn sarif_report(problems: &[Problem]) -> Value {
+    let mut rules = problems.iter().filter_map(|problem| code(&problem.diagnostic)).collect::<Vec<_>>();
+    rules.sort();
+    rules.dedup();
+
+    let results = problems
+        .iter()
+        .map(|problem| {
+            let level = match problem.diagnostic.severity {
+                Some(DiagnosticSeverity::WARNING) => "warning",
+                Some(DiagnosticSeverity::INFORMATION | DiagnosticSeverity::HINT) => "note",
+                _ => "error",
+            };
+            let (line, column, end_line, end_column) = problem.location;
+
+            json!({
+                "ruleId": code(&problem.diagnostic),
+                "level": level,
+                "message": { "text": problem.diagnostic.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": problem.path },
+                        "region": {
+                            "startLine": line,
+                            "startColumn": column,
+                            "endLine": end_line,
+                            "endColumn": end_column,
+                        },
+                    },
+                }],
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules.iter().map(|rule| json!({ "id": rule })).collect::<Vec<_>>(),
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::temp_files;
+    use tower_lsp::lsp_types::Range;
+
+    fn problem(source: &str, start: (u32, u32), end: (u32, u32), severity: DiagnosticSeverity, code: &str) -> Problem {
+        let diagnostic = Diagnostic {
+            range: Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1)),
+            severity: Some(severity),
+            code: Some(NumberOrString::String(code.to_string())),
+            message: format!("{code}: 50%, see\nbelow"),
+            ..Default::default()
+        };
+
+        Problem::new("views/index.rs.html".to_string(), source, diagnostic)
+    }
+
+    #[test]
+    fn counts_columns_in_characters() {
+        // `é` is two bytes and one utf-16 unit, `😀` four bytes and two units
+        let dir = temp_files(&[
+            ("Cargo.toml", "[package]\nname = \"app\"\n"),
+            ("views/index.rs.html", "<p>\n<b>é😀 @include(\"missing.rs.html\")</b>\n</p>\n"),
+        ]);
+        let root = dir.path().canonicalize().unwrap();
+
+        let problems = check(&AppState::setup(), &root).unwrap();
+        let problem = problems.iter().find(|problem| problem.path == "views/index.rs.html").unwrap();
+
+        assert_eq!(problem.diagnostic.message, "File `missing.rs.html` not found");
+        assert_eq!(problem.location, (2, 17, 2, 32));
+        assert_eq!(exit_code(&problems), 1);
+    }
+
+    #[test]
+    fn reports_json() {
+        let problems = [problem("@x\n", (0, 0), (0, 2), DiagnosticSeverity::WARNING, "unused")];
+
+        assert_eq!(
+            json_report(&problems),
+            json!([{
+                "file": "views/index.rs.html",
+                "line": 1,
+                "column": 1,
+                "endLine": 1,
+                "endColumn": 3,
+                "severity": "warning",
+                "code": "unused",
+                "message": "unused: 50%, see\nbelow",
+            }])
+        );
+    }
+
+    #[test]
+    fn reports_github_annotations() {
+        let problems = [
+            problem("@x\n", (0, 0), (0, 2), DiagnosticSeverity::ERROR, "a:b"),
+            problem("@x\n", (0, 1), (0, 2), DiagnosticSeverity::HINT, "hint"),
+        ];
+
+        assert_eq!(
+            github_annotations(&problems),
+            [
+                "::error file=views/index.rs.html,line=1,col=1,endLine=1,endColumn=3,title=a%3Ab::a:b: 50%25, see%0Abelow",
+                "::notice file=views/index.rs.html,line=1,col=2,endLine=1,endColumn=3,title=hint::hint: 50%25, see%0Abelow",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_sarif() {
+        let problems = [
+            problem("@x\n", (0, 0), (0, 2), DiagnosticSeverity::ERROR, "unknown"),
+            problem("@x\n", (0, 1), (0, 2), DiagnosticSeverity::INFORMATION, "unknown"),
+        ];
+
+        let report = sarif_report(&problems);
+        let run = &report["runs"][0];
+        assert_eq!(report["version"], "2.1.0");
+        assert_eq!(run["tool"]["driver"]["rules"], json!([{ "id": "unknown" }]));
+        assert_eq!(run["results"][0]["level"], "error");
+        assert_eq!(run["results"][1]["level"], "note");
+        assert_eq!(
+            run["results"][1]["locations"][0]["physicalLocation"],
+            json!({
+                "artifactLocation": { "uri": "views/index.rs.html" },
+                "region": { "startLine": 1, "startColumn": 2, "endLine": 1, "endColumn": 3 },
+            })
+        );
+    }
+
+    #[test]
+    fn fails_only_on_errors() {
+        let warning = problem("", (0, 0), (0, 0), DiagnosticSeverity::WARNING, "warning");
+        let error = problem("", (0, 0), (0, 0), DiagnosticSeverity::ERROR, "error");
+
+        assert_eq!(exit_code(&[]), 0);
+        assert_eq!(exit_code(&[warning]), 0);
+        assert_eq!(exit_code(&[error]), 1);
+    }
+}
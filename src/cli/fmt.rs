@@ -0,0 +1,189 @@
+use crate::app_state::AppState;
+use crate::app_state::workspace::Workspace;
+use clap::Args;
+use similar::TextDiff;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use tower_lsp::lsp_types::FormattingOptions;
+
+const DIFF_CONTEXT: usize = 3;
+
+#[derive(Args)]
+pub struct FmtArgs {
+    /// Views or workspace directories to format, `-` reads from stdin
+    #[arg(default_value = ".")]
+    paths: Vec<PathBuf>,
+
+    /// Print a diff instead of rewriting files and fail if anything would change
+    #[arg(long)]
+    check: bool,
+
+    /// Number of spaces per indentation level
+    #[arg(long, default_value_t = 4)]
+    tab_size: u32,
+
+    /// Indent with tabs instead of spaces
+    #[arg(long)]
+    use_tabs: bool,
+}
+
+pub fn run(args: FmtArgs) -> i32 {
+    let options = FormattingOptions {
+        tab_size: args.tab_size,
+        insert_spaces: !args.use_tabs,
+        insert_final_newline: Some(true),
+        trim_final_newlines: Some(true),
+        ..Default::default()
+    };
+
+    let state = AppState::setup();
+    let mut changed = false;
+    let mut failed = false;
+
+    for path in &args.paths {
+        if path.as_os_str() == "-" {
+            match format_stdin(&state, &options, args.check) {
+                Ok(stdin_changed) => changed |= stdin_changed,
+                Err(e) => {
+                    eprintln!("Couldn't format stdin: {}", e);
+                    failed = true;
+                }
+            }
+            continue;
+        }
+
+        let files = match view_files(&state, path) {
+            Ok(files) => files,
+            Err(e) => {
+                eprintln!("Couldn't open {}: {}", path.display(), e);
+                failed = true;
+                continue;
+            }
+        };
+
+        for file in files {
+            match format_file(&state, &options, &file, args.check) {
+                Ok(file_changed) => changed |= file_changed,
+                Err(e) => {
+                    eprintln!("Couldn't format {}: {}", file.display(), e);
+                    failed = true;
+                }
+            }
+        }
+    }
+
+    if failed { 2 } else { i32::from(args.check && changed) }
+}
+
+fn view_files(state: &AppState, path: &Path) -> Result<Vec<PathBuf>, String> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let root = path.canonicalize().map_err(|e| e.to_string())?;
+
+    // a views directory on its own has no manifest to read the view paths from
+    if !root.join("Cargo.toml").is_file() {
+        if !root.is_dir() {
+            return Err("not a directory".to_string());
+        }
+
+        let mut files = Vec::new();
+        Workspace::collect_view_files(&root, &mut files);
+        files.sort();
+        return Ok(files);
+    }
+
+    let mut workspace = state.workspace.write().map_err(|_| "workspace is locked".to_string())?;
+    workspace.load_folder(&root)?;
+
+    Ok(workspace.folder_view_files(&root))
+}
+
+fn format_file(state: &AppState, options: &FormattingOptions, path: &Path, check: bool) -> Result<bool, String> {
+    let source = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let formatted = state.format_source(&source, options).ok_or("couldn't parse the view")?;
+
+    if formatted == source {
+        return Ok(false);
+    }
+
+    if check {
+        print!("{}", unified_diff(&path.to_string_lossy(), &source, &formatted));
+    } else {
+        fs::write(path, formatted).map_err(|e| e.to_string())?;
+        println!("Formatted {}", path.display());
+    }
+
+    Ok(true)
+}
+
+fn format_stdin(state: &AppState, options: &FormattingOptions, check: bool) -> Result<bool, String> {
+    let mut source = String::new();
+    std::io::stdin().read_to_string(&mut source).map_err(|e| e.to_string())?;
+
+    let formatted = state.format_source(&source, options).ok_or("couldn't parse the view")?;
+
+    if check {
+        print!("{}", unified_diff("<stdin>", &source, &formatted));
+    } else {
+        std::io::stdout().write_all(formatted.as_bytes()).map_err(|e| e.to_string())?;
+    }
+
+    Ok(formatted != source)
+}
+
+// lines are compared with their line endings, so a missing final newline shows up as a change
+fn unified_diff(name: &str, old: &str, new: &str) -> String {
+    TextDiff::from_lines(old, new)
+        .unified_diff()
+        .context_radius(DIFF_CONTEXT)
+        .header(name, name)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::temp_files;
+
+    #[test]
+    fn diffs_changed_lines() {
+        let diff = unified_diff("view.rs.html", "<div>\n<p>a</p>\n</div>\n", "<div>\n    <p>a</p>\n</div>\n");
+
+        assert_eq!(
+            diff,
+            "--- view.rs.html\n+++ view.rs.html\n@@ -1,3 +1,3 @@\n <div>\n-<p>a</p>\n+    <p>a</p>\n </div>\n"
+        );
+    }
+
+    #[test]
+    fn diffs_missing_final_newline() {
+        let diff = unified_diff("view.rs.html", "<p>a</p>", "<p>a</p>\n");
+
+        assert!(diff.contains("@@ -1 +1 @@"));
+        assert!(diff.contains("-<p>a</p>\n\\ No newline at end of file\n"));
+        assert!(diff.contains("+<p>a</p>\n"));
+    }
+
+    #[test]
+    fn formats_only_the_given_folder() {
+        let dir = temp_files(&[
+            ("a/Cargo.toml", "[package]\nname = \"a\"\n"),
+            ("a/views/a.rs.html", "<p>a</p>\n"),
+            ("b/Cargo.toml", "[package]\nname = \"b\"\n"),
+            ("b/views/b.rs.html", "<p>a</p>\n"),
+            ("plain/nested/c.rs.html", "<p>a</p>\n"),
+        ]);
+        let root = dir.path().canonicalize().unwrap();
+
+        let state = AppState::setup();
+        assert_eq!(view_files(&state, &root.join("a")).unwrap(), [root.join("a/views/a.rs.html")]);
+        assert_eq!(view_files(&state, &root.join("b")).unwrap(), [root.join("b/views/b.rs.html")]);
+        assert_eq!(
+            view_files(&state, &root.join("plain")).unwrap(),
+            [root.join("plain/nested/c.rs.html")]
+        );
+    }
+}
@@ -37,4 +37,36 @@ pub const SEMANTIC_TOKEN_MODIFIERS: [SemanticTokenModifier; 10] = [
     SemanticTokenModifier::MODIFICATION,
     SemanticTokenModifier::DOCUMENTATION,
     SemanticTokenModifier::DEFAULT_LIBRARY,
-];
\ No newline at end of file
+];
+
+pub const DIRECTIVE_DOCS: [(&str, &str); 8] = [
+    (
+        "include",
+        "Inserts another template in place: `@include(\"path/to/other.rs.html\")`.",
+    ),
+    (
+        "extends",
+        "Renders this view inside a layout: `@extends(\"layout.rs.html\")`. Without a path the default layout is used.",
+    ),
+    (
+        "section",
+        "Defines a section for the layout: `@section(\"name\", \"value\")` or `@section name { ... }`.",
+    ),
+    ("render", "Renders a section defined by the child view: `@render(name)`."),
+    (
+        "section_body",
+        "Renders the body of the child view, everything outside its sections, at this place in the layout: `@section_body()`.",
+    ),
+    (
+        "child_content",
+        "Renders the content passed between the opening and closing tags of a component.",
+    ),
+    (
+        "raw",
+        "Outputs its content as is, without parsing it as a template: `@raw { ... }`.",
+    ),
+    (
+        "use",
+        "Imports a template as a component: `@use \"path/to/component.rs.html\" as Component`.",
+    ),
+];
@@ -0,0 +1,91 @@
+use std::fs::{File, OpenOptions};
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{Registry, fmt, reload};
+
+static LEVEL_HANDLE: OnceLock<reload::Handle<LevelFilter, Registry>> = OnceLock::new();
+static LOG_FILE: Mutex<Option<File>> = Mutex::new(None);
+
+// writes to the log file once one is set, to stderr until then
+struct LogWriter;
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match LOG_FILE.lock() {
+            Ok(mut file) if file.is_some() => file.as_mut().map_or(Ok(0), |file| file.write(buf)),
+            _ => std::io::stderr().write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match LOG_FILE.lock() {
+            Ok(mut file) if file.is_some() => file.as_mut().map_or(Ok(()), |file| file.flush()),
+            _ => std::io::stderr().flush(),
+        }
+    }
+}
+
+pub fn init(level: LevelFilter, file: Option<&Path>) {
+    let (filter, handle) = reload::Layer::new(level);
+
+    if let Some(file) = file
+        && let Err(e) = set_file(file)
+    {
+        eprintln!("Couldn't open log file {}: {}", file.display(), e);
+    }
+
+    let ansi = file.is_none() && std::io::stderr().is_terminal();
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_ansi(ansi).with_writer(|| LogWriter))
+        .init();
+
+    let _ = LEVEL_HANDLE.set(handle);
+}
+
+pub fn set_level(level: &str) -> Result<(), String> {
+    let level = level.parse::<LevelFilter>().map_err(|e| e.to_string())?;
+    let handle = LEVEL_HANDLE.get().ok_or("logging is not initialized")?;
+
+    handle.modify(|filter| *filter = level).map_err(|e| e.to_string())
+}
+
+pub fn set_file(path: &Path) -> Result<(), String> {
+    let file = OpenOptions::new().create(true).append(true).open(path).map_err(|e| e.to_string())?;
+
+    let mut log_file = LOG_FILE.lock().map_err(|_| "log file is locked".to_string())?;
+    *log_file = Some(file);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::temp_files;
+    use std::fs;
+    use tracing::level_filters::STATIC_MAX_LEVEL;
+
+    #[test]
+    fn writes_trace_events_after_raising_the_level() {
+        // trace events have to be compiled in, also in release builds
+        assert_eq!(STATIC_MAX_LEVEL, LevelFilter::TRACE);
+
+        let dir = temp_files(&[]);
+        let path = dir.path().join("rshtml.log");
+        init(LevelFilter::INFO, Some(&path));
+
+        tracing::trace!("hidden trace event");
+        set_level("trace").unwrap();
+        tracing::trace!("visible trace event");
+
+        let log = fs::read_to_string(&path).unwrap();
+        assert!(!log.contains("hidden trace event"));
+        assert!(log.contains("visible trace event"));
+    }
+}
@@ -1,71 +1,52 @@
 mod app_state;
 mod backend;
+mod cli;
 mod consts;
+mod logging;
+#[cfg(test)]
+mod test_utils;
+mod transport;
 
-use crate::app_state::AppState;
-use crate::backend::Backend;
-use tower_lsp::{LspService, Server};
+use crate::transport::Transport;
 use clap::Parser;
+use std::path::PathBuf;
+use tracing::level_filters::LevelFilter;
 
 #[derive(Parser)]
 #[command(version)]
-struct Cli{
-    #[arg(long)]
-    stdio: bool,
-}
+struct Cli {
+    #[command(flatten)]
+    transport: Transport,
+
+    /// Log level: off, error, warn, info, debug or trace
+    #[arg(long, global = true)]
+    log_level: Option<LevelFilter>,
 
-#[cfg(debug_assertions)]
-use tracing::debug;
+    /// Write logs to this file instead of stderr
+    #[arg(long, global = true)]
+    log_file: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<cli::Command>,
+}
 
 #[tokio::main]
 async fn main() {
-    Cli::parse();
+    let cli = Cli::parse();
 
-    let filter_level = if cfg!(debug_assertions) {
-        tracing::Level::DEBUG
+    let default_level = if cli.command.is_some() {
+        LevelFilter::WARN
+    } else if cfg!(debug_assertions) {
+        LevelFilter::DEBUG
     } else {
-        tracing::Level::INFO
+        LevelFilter::INFO
     };
 
-    tracing_subscriber::fmt()
-        .with_max_level(filter_level)
-        .with_writer(std::io::stderr)
-        .init();
-
-    #[cfg(debug_assertions)]
-    tcp_connection().await;
-
-    #[cfg(not(debug_assertions))]
-    stdio_connection().await;
-}
-
-#[cfg(debug_assertions)]
-async fn tcp_connection() {
-    let addr = "127.0.0.1:9257";
-
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    debug!("LSP server listens on TCP server: {}", addr);
-
-    loop {
-        let (stream, client_addr) = listener.accept().await.unwrap();
-        debug!("New client connected: {}", client_addr);
-
-        let (service, socket) = LspService::new(|client| Backend::new(client, AppState::setup()));
+    logging::init(cli.log_level.unwrap_or(default_level), cli.log_file.as_deref());
 
-        let (read, write) = tokio::io::split(stream);
-
-        tokio::spawn(async move {
-            Server::new(read, write, socket).serve(service).await;
-            debug!("Client session ended: {}", client_addr);
-        });
+    if let Some(command) = cli.command {
+        std::process::exit(command.run());
     }
-}
-
-#[cfg(not(debug_assertions))]
-async fn stdio_connection() {
-    let stdin = tokio::io::stdin();
-    let stdout = tokio::io::stdout();
 
-    let (service, socket) = LspService::new(|client| Backend::new(client, AppState::setup()));
-    Server::new(stdin, stdout, socket).serve(service).await;
+    cli.transport.serve().await;
 }
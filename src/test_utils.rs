@@ -0,0 +1,42 @@
+use crate::app_state::AppState;
+use crate::backend::Backend;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+use tower_lsp::lsp_types::{DidOpenTextDocumentParams, TextDocumentItem, Url};
+use tower_lsp::{LanguageServer, LspService};
+
+// a temporary folder with the given files, paths ending in `/` are empty directories;
+// it is removed when dropped, also when an assertion fails
+pub fn temp_files(files: &[(&str, &str)]) -> TempDir {
+    let dir = tempfile::Builder::new().prefix("rshtml-").tempdir().unwrap();
+
+    for (path, content) in files {
+        let path = dir.path().join(path);
+        if path.to_string_lossy().ends_with('/') {
+            fs::create_dir_all(&path).unwrap();
+            continue;
+        }
+
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    dir
+}
+
+// a backend over the folder with every view indexed, the client socket is dropped so nothing is sent
+pub fn test_backend(root: &Path) -> LspService<Backend> {
+    let (service, _) = LspService::new(|client| Backend::new(client, AppState::setup()));
+    service.inner().state.workspace.write().unwrap().load_folder(root).unwrap();
+    service.inner().state.index_workspace_blocking();
+    service
+}
+
+pub async fn open_view(backend: &Backend, path: &Path) -> Url {
+    let uri = Url::from_file_path(path).unwrap();
+    let text = fs::read_to_string(path).unwrap();
+    let text_document = TextDocumentItem::new(uri.clone(), "rshtml".to_string(), 1, text);
+    backend.did_open(DidOpenTextDocumentParams { text_document }).await;
+    uri
+}
@@ -0,0 +1,150 @@
+use crate::app_state::AppState;
+use crate::backend::Backend;
+use clap::Args;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tower_lsp::{LspService, Server};
+use tracing::{debug, error, info};
+
+#[derive(Args)]
+#[group(multiple = false)]
+pub struct Transport {
+    /// Talk to a single client over stdin and stdout (the default)
+    #[arg(long)]
+    stdio: bool,
+
+    /// Listen for clients on 127.0.0.1:<PORT>
+    #[arg(long)]
+    port: Option<u16>,
+
+    /// Listen for clients on the given address, e.g. 0.0.0.0:9257
+    #[arg(long)]
+    listen: Option<SocketAddr>,
+
+    /// Listen for clients on a unix domain socket
+    #[cfg(unix)]
+    #[arg(long)]
+    socket: Option<PathBuf>,
+
+    /// Connect to a pipe created by the client: a unix socket path on unix, a named pipe like \\.\pipe\rshtml on windows
+    #[arg(long)]
+    pipe: Option<PathBuf>,
+}
+
+impl Transport {
+    pub async fn serve(self) {
+        #[cfg(unix)]
+        if let Some(path) = self.socket {
+            return unix_socket(&path).await;
+        }
+
+        if let Some(port) = self.port {
+            tcp(SocketAddr::from(([127, 0, 0, 1], port))).await
+        } else if let Some(addr) = self.listen {
+            tcp(addr).await
+        } else if let Some(path) = self.pipe {
+            pipe(&path).await
+        } else {
+            serve(tokio::io::stdin(), tokio::io::stdout()).await
+        }
+    }
+}
+
+async fn serve<I, O>(read: I, write: O)
+where
+    I: AsyncRead + Unpin,
+    O: AsyncWrite,
+{
+    let (service, socket) = LspService::build(|client| Backend::new(client, AppState::setup()))
+        .custom_method("$/setTrace", Backend::set_trace)
+        .finish();
+    Server::new(read, write, socket).serve(service).await;
+}
+
+async fn tcp(addr: SocketAddr) {
+    let listener = match tokio::net::TcpListener::bind(addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("Couldn't listen on {}: {}", addr, e);
+            return;
+        }
+    };
+    info!("LSP server listens on TCP server: {}", addr);
+
+    loop {
+        let (stream, client_addr) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                error!("Couldn't accept client: {}", e);
+                continue;
+            }
+        };
+        debug!("New client connected: {}", client_addr);
+
+        tokio::spawn(async move {
+            let (read, write) = tokio::io::split(stream);
+            serve(read, write).await;
+            debug!("Client session ended: {}", client_addr);
+        });
+    }
+}
+
+#[cfg(unix)]
+async fn unix_socket(path: &Path) {
+    use tokio::net::{UnixListener, UnixStream};
+
+    // a socket file left behind by an analyzer that is no longer running blocks the bind
+    if path.exists() && UnixStream::connect(path).await.is_err() {
+        let _ = std::fs::remove_file(path);
+    }
+
+    let listener = match UnixListener::bind(path) {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("Couldn't listen on {}: {}", path.display(), e);
+            return;
+        }
+    };
+    info!("LSP server listens on unix socket: {}", path.display());
+
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                error!("Couldn't accept client: {}", e);
+                continue;
+            }
+        };
+        debug!("New client connected");
+
+        tokio::spawn(async move {
+            let (read, write) = tokio::io::split(stream);
+            serve(read, write).await;
+            debug!("Client session ended");
+        });
+    }
+}
+
+async fn pipe(path: &Path) {
+    #[cfg(unix)]
+    let stream = tokio::net::UnixStream::connect(path).await;
+
+    #[cfg(windows)]
+    let stream = tokio::net::windows::named_pipe::ClientOptions::new().open(path);
+
+    #[cfg(not(any(unix, windows)))]
+    let stream: std::io::Result<tokio::io::DuplexStream> = Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "pipes are only supported on unix and windows",
+    ));
+
+    match stream {
+        Ok(stream) => {
+            debug!("Connected to pipe: {}", path.display());
+            let (read, write) = tokio::io::split(stream);
+            serve(read, write).await;
+        }
+        Err(e) => error!("Couldn't connect to pipe {}: {}", path.display(), e),
+    }
+}